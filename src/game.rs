@@ -1,34 +1,146 @@
 use bevy::{
-    input::{keyboard::KeyboardInput, ButtonState},
     prelude::*,
     render::{mesh::Indices, render_resource::PrimitiveTopology},
-    sprite::MaterialMesh2dBundle,
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+    time::Stopwatch,
+    utils::HashMap,
 };
-
+use rand::Rng;
 use std::f32::consts::PI;
 
-use super::{despawn_screen, DisplayQuality, GameState, Volume, TEXT_COLOR};
+use super::{
+    despawn_screen,
+    highscore::{HighScore, HighScores},
+    GamePhase, GameState, RenderStyle, TEXT_COLOR,
+};
+
+mod background;
+mod camera;
+mod collision;
+mod hud;
+mod hyperspace;
+mod particle;
+mod powerup;
+mod quality;
+mod saucer;
+mod shield;
+mod shop;
+mod spatial_hash;
+mod spawn;
+mod style;
+mod thruster;
+mod wave;
+mod wrap;
 
-// This plugin will contain the game. In this case, it's just be a screen that will
-// display the current settings for 5 seconds before returning to the menu
+use camera::CameraEffect;
+use collision::{bounding_radius, convex_hull, Collider, Hull};
+use hyperspace::InHyperspace;
+use particle::EmitParticles;
+use powerup::{
+    collect_power_ups, drop_power_up, Piercing, PowerUpKind, PowerUpTable, PowerUps,
+    TRIPLE_SHOT_ANGLE,
+};
+use quality::QualitySettings;
+use saucer::{
+    detect_saucer_bullet_collision, detect_saucer_collision, fire_saucers, steer_saucers,
+    SaucerBullet, SaucerSize,
+};
+use shield::{create_shield_mesh, spawn_shield_ring, update_shield, Shield};
+use shop::Upgrades;
+use spatial_hash::{rebuild_spatial_hash, SpatialHash};
+use spawn::split_velocities;
+use style::{asteroid_look, saucer_look, starship_look, SpriteTextures};
+use thruster::{create_flame_mesh, spawn_flame};
+use wave::{Wave, WaveCurve};
+use wrap::{spawn_wrap_ghosts, sync_wrap_ghosts, wrap_position, wrapped_delta};
+
+// This plugin contains the game itself. When the last ship is lost, a game over screen
+// is displayed for a few seconds before returning to the menu
 pub struct GamePlugin;
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(update_position)
-        .add_system(remove_bullet)
-        .add_system(decelerate_starship)
-        .add_system(sync_translate_transform.after(update_position))
-        .add_system(sync_asteroid_scale_transform)
-        .add_system(sync_starship_rotation_transform)
-        .add_system(keyboard_events)
-        .add_system(detect_starship_asteroid_collision)
-        .add_system(detect_bullet_asteroid_collision)
-        .add_systems((
-            setup_game.in_schedule(OnEnter(GameState::Game)),
-            // game.in_set(OnUpdate(GameState::Game)),
-            // despawn_screen::<OnGameScreen>.in_schedule(OnExit(GameState::Game)),
-        ));
+        app.add_plugin(background::BackgroundPlugin)
+            .add_plugin(camera::GameCameraPlugin)
+            .add_plugin(hud::HudPlugin)
+            .add_plugin(hyperspace::HyperspacePlugin)
+            .add_plugin(particle::ParticlePlugin)
+            .add_plugin(powerup::PowerUpPlugin)
+            .add_plugin(quality::QualityPlugin)
+            .add_plugin(saucer::SaucerPlugin)
+            .add_plugin(shield::ShieldPlugin)
+            .add_plugin(shop::ShopPlugin)
+            .add_plugin(spawn::SpawnPlugin)
+            .add_plugin(style::RenderStylePlugin)
+            .add_plugin(thruster::ThrusterPlugin)
+            .add_plugin(wave::WavePlugin)
+            .insert_resource(FixedTime::new_from_secs(PHYSICS_TIMESTEP))
+            .init_resource::<AsteroidShapeSettings>()
+            .init_resource::<SpatialHash>()
+            .init_resource::<Weapon>()
+            .add_startup_system(setup_game_assets)
+            .add_system(setup_game.in_schedule(OnEnter(GameState::Game)))
+            // Physics is stepped on a fixed timestep, so the game plays the same at any frame rate
+            .add_systems(
+                (
+                    store_previous_state,
+                    steer_starship,
+                    decelerate_starship,
+                    update_shield,
+                    fire_bullets,
+                    steer_saucers,
+                    fire_saucers,
+                    update_position,
+                    rebuild_spatial_hash,
+                    remove_bullet,
+                )
+                    .chain()
+                    .distributive_run_if(in_state(GamePhase::Playing))
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            )
+            // Then collisions. What is destroyed by one collision system must be gone before the
+            // next one runs, so that nothing is destroyed twice in the same step
+            .add_systems(
+                (
+                    detect_starship_asteroid_collision.after(remove_bullet),
+                    apply_system_buffers,
+                    detect_bullet_asteroid_collision,
+                    apply_system_buffers,
+                    detect_saucer_collision,
+                    apply_system_buffers,
+                    detect_saucer_bullet_collision,
+                    apply_system_buffers,
+                    collect_power_ups,
+                )
+                    .chain()
+                    .distributive_run_if(in_state(GamePhase::Playing))
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            )
+            // Systems running every frame while the player is in control of the game
+            .add_systems(
+                (
+                    sync_translate_transform,
+                    sync_asteroid_scale_transform,
+                    sync_starship_rotation_transform,
+                    sync_wrap_ghosts
+                        .after(sync_translate_transform)
+                        .after(sync_asteroid_scale_transform)
+                        .after(sync_starship_rotation_transform),
+                    keyboard_events,
+                    respawn_starship,
+                    blink_invulnerable_starship,
+                    tick_time_survived,
+                    pause_on_escape,
+                )
+                    .in_set(OnUpdate(GamePhase::Playing)),
+            )
+            // Systems to handle the game over screen
+            .add_systems((
+                game_over_setup.in_schedule(OnEnter(GamePhase::GameOver)),
+                game_over.in_set(OnUpdate(GamePhase::GameOver)),
+            ))
+            // When leaving the game, despawn the whole game world
+            .add_system(despawn_screen::<OnGameScreen>.in_schedule(OnExit(GameState::Game)));
     }
 }
 const VIEWPORT_WIDTH: usize = 1280;
@@ -37,13 +149,42 @@ const VIEWPORT_MAX_X: f32 = VIEWPORT_WIDTH as f32 / 2.0;
 const VIEWPORT_MIN_X: f32 = -VIEWPORT_MAX_X;
 const VIEWPORT_MAX_Y: f32 = VIEWPORT_HEIGHT as f32 / 2.0;
 const VIEWPORT_MIN_Y: f32 = -VIEWPORT_MAX_Y;
-const ASTEROID_VELOCITY: f32 = 2.0;
-const BULLET_VELOCITY: f32 = 6.0;
+// All speeds are per second, distances in pixels
+const PHYSICS_TIMESTEP: f32 = 1.0 / 60.0;
+const ASTEROID_VELOCITY: f32 = 120.0;
+const BULLET_VELOCITY: f32 = 360.0;
 const BULLET_DISTANCE: f32 = VIEWPORT_HEIGHT as f32 * 0.8;
-const STARSHIP_ROTATION_SPEED: f32 = 5.0 * 2.0 * PI / 360.0;
-const STARSHIP_ACCELERATION: f32 = 0.2;
-const STARSHIP_DECELERATION: f32 = 0.01;
-const STARSHIP_MAX_VELOCITY: f32 = 10.0;
+const STARSHIP_ROTATION_SPEED: f32 = 300.0 * 2.0 * PI / 360.0;
+const STARSHIP_ACCELERATION: f32 = 720.0;
+// Rate at which the ship loses speed when not thrusting, the velocity decays as exp(-rate * t)
+const STARSHIP_DECELERATION: f32 = 0.6;
+const STARSHIP_MAX_VELOCITY: f32 = 600.0;
+// Seconds between two shots, and number of bullets the ship can have on the screen at once
+const WEAPON_COOLDOWN: f32 = 0.2;
+const WEAPON_MAX_BULLETS: usize = 4;
+// Whether bullets move along with the ship on top of their own velocity, classic bullets don't
+const WEAPON_INHERIT_VELOCITY: bool = false;
+const STARSHIP_SCALE: f32 = 50.0;
+// Outline of the ship at a scale of 1.0, pointing up
+const STARSHIP_VERTICES: [Vec2; 3] = [
+    Vec2::new(0.0, 0.5),
+    Vec2::new(-0.25, -0.5),
+    Vec2::new(0.25, -0.5),
+];
+const BULLET_SCALE: f32 = 5.0;
+// Number of different asteroid outlines generated on startup
+const ASTEROID_SHAPE_VARIANTS: usize = 16;
+// Deepest dent drawn along the edges of an asteroid, relative to its distance from the center
+const ASTEROID_DENT_DEPTH: f32 = 0.08;
+const STARSHIP_LIVES: u32 = 3;
+const STARSHIP_RESPAWN_DELAY: f32 = 2.0;
+const STARSHIP_INVULNERABILITY_DURATION: f32 = 3.0;
+const STARSHIP_BLINK_PERIOD: f32 = 0.1;
+const GAME_OVER_DURATION: f32 = 3.0;
+// Camera shake added when the ship is destroyed, from 0.0 to 1.0
+const STARSHIP_DESTROYED_TRAUMA: f32 = 0.8;
+// Seconds the game freezes for when a big asteroid is destroyed
+const HIT_STOP_DURATION: f32 = 0.08;
 
 // Tag component used to tag entities added on the game screen
 #[derive(Component)]
@@ -52,14 +193,27 @@ struct OnGameScreen;
 #[derive(Resource, Deref, DerefMut)]
 struct GameTimer(Timer);
 
-fn game_setup(
+fn game_over_setup(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
-    display_quality: Res<DisplayQuality>,
-    volume: Res<Volume>,
+    mut high_scores: ResMut<HighScores>,
+    score: Res<Score>,
+    wave: Res<Wave>,
+    time_survived: Res<TimeSurvived>,
 ) {
     let font = asset_server.load("fonts/FiraSans-Bold.ttf");
 
+    // Record the game in the high-score table straight away, so it isn't lost if the
+    // player quits during the game over screen
+    let rank = high_scores.insert(HighScore::new(score.0, wave.0, time_survived.elapsed()));
+    if rank.is_some() {
+        high_scores.save();
+    }
+    let message = match rank {
+        Some(rank) => format!("New high score: #{rank}"),
+        None => "Will be back to the menu shortly...".to_string(),
+    };
+
     commands
         .spawn((
             NodeBundle {
@@ -75,15 +229,10 @@ fn game_setup(
             OnGameScreen,
         ))
         .with_children(|parent| {
-            // First create a `NodeBundle` for centering what we want to display
             parent
                 .spawn(NodeBundle {
                     style: Style {
-                        // This will display its children in a column, from top to bottom
                         flex_direction: FlexDirection::Column,
-                        // `align_items` will align children on the cross axis. Here the main axis is
-                        // vertical (column), so the cross axis is horizontal. This will center the
-                        // children
                         align_items: AlignItems::Center,
                         ..default()
                     },
@@ -91,10 +240,9 @@ fn game_setup(
                     ..default()
                 })
                 .with_children(|parent| {
-                    // Display two lines of text, the second one with the current settings
                     parent.spawn(
                         TextBundle::from_section(
-                            "Will be back to the menu shortly...",
+                            "Game Over",
                             TextStyle {
                                 font: font.clone(),
                                 font_size: 80.0,
@@ -107,32 +255,14 @@ fn game_setup(
                         }),
                     );
                     parent.spawn(
-                        TextBundle::from_sections([
-                            TextSection::new(
-                                format!("quality: {:?}", *display_quality),
-                                TextStyle {
-                                    font: font.clone(),
-                                    font_size: 60.0,
-                                    color: Color::BLUE,
-                                },
-                            ),
-                            TextSection::new(
-                                " - ",
-                                TextStyle {
-                                    font: font.clone(),
-                                    font_size: 60.0,
-                                    color: TEXT_COLOR,
-                                },
-                            ),
-                            TextSection::new(
-                                format!("volume: {:?}", *volume),
-                                TextStyle {
-                                    font: font.clone(),
-                                    font_size: 60.0,
-                                    color: Color::GREEN,
-                                },
-                            ),
-                        ])
+                        TextBundle::from_section(
+                            message,
+                            TextStyle {
+                                font,
+                                font_size: 40.0,
+                                color: TEXT_COLOR,
+                            },
+                        )
                         .with_style(Style {
                             margin: UiRect::all(Val::Px(50.0)),
                             ..default()
@@ -140,18 +270,20 @@ fn game_setup(
                     );
                 });
         });
-    // Spawn a 5 seconds timer to trigger going back to the menu
-    commands.insert_resource(GameTimer(Timer::from_seconds(5.0, TimerMode::Once)));
+    // Spawn a timer to trigger going back to the menu
+    commands.insert_resource(GameTimer(Timer::from_seconds(GAME_OVER_DURATION, TimerMode::Once)));
 }
 
 // Tick the timer, and change state when finished
-fn game(
+fn game_over(
     time: Res<Time>,
     mut game_state: ResMut<NextState<GameState>>,
+    mut game_phase: ResMut<NextState<GamePhase>>,
     mut timer: ResMut<GameTimer>,
 ) {
     if timer.tick(time.delta()).finished() {
         game_state.set(GameState::Menu);
+        game_phase.set(GamePhase::Disabled);
     }
 }
 
@@ -163,36 +295,213 @@ Small,
 }
 
 impl AsteroidSize {
-fn scale(&self) -> f32 {
-    match self {
-    AsteroidSize::Big => 100.0,
-    AsteroidSize::Medium => 65.0,
-    AsteroidSize::Small => 30.0,
+    fn scale(&self) -> f32 {
+        match self {
+            AsteroidSize::Big => 100.0,
+            AsteroidSize::Medium => 65.0,
+            AsteroidSize::Small => 30.0,
+        }
+    }
+
+    // Classic arcade values: the smaller the asteroid, the harder it is to hit
+    fn points(&self) -> u32 {
+        match self {
+            AsteroidSize::Big => 20,
+            AsteroidSize::Medium => 50,
+            AsteroidSize::Small => 100,
+        }
+    }
+
+    // Camera shake added when an asteroid of this size is destroyed
+    fn trauma(&self) -> f32 {
+        match self {
+            AsteroidSize::Big => 0.35,
+            AsteroidSize::Medium => 0.25,
+            AsteroidSize::Small => 0.15,
+        }
     }
-}
 }
 
 #[derive(Component)]
 struct Starship {
-rotation_angle: f32,
+    rotation_angle: f32,
+    // Rotation at the previous physics step, used to interpolate the rendering
+    previous_rotation_angle: f32,
 }
 
 impl Starship {
-fn direction(&self) -> Vec2 {
-    let (y, x) = (self.rotation_angle + PI / 2.0).sin_cos();
+    fn direction(&self) -> Vec2 {
+        let (y, x) = (self.rotation_angle + PI / 2.0).sin_cos();
+
+        Vec2::new(x, y)
+    }
+}
 
-    Vec2::new(x, y)
+// How a ship handles. Every ship starts from the constants above, improved by the upgrades
+// bought in the shop
+#[derive(Component, Debug, Clone)]
+struct StarshipStats {
+    acceleration: f32,
+    max_velocity: f32,
+    rotation_speed: f32,
 }
+
+impl Default for StarshipStats {
+    fn default() -> Self {
+        StarshipStats {
+            acceleration: STARSHIP_ACCELERATION,
+            max_velocity: STARSHIP_MAX_VELOCITY,
+            rotation_speed: STARSHIP_ROTATION_SPEED,
+        }
+    }
+}
+
+// How a ship fires. It will be a resource in the app, the weapon every ship starts with, and a
+// component on each ship, improved by the upgrades bought in the shop. Power-ups modify it
+// further while they last
+#[derive(Resource, Component, Debug, Clone)]
+struct Weapon {
+    // Seconds between two shots
+    cooldown: f32,
+    // Whether holding the fire key keeps firing, instead of firing once per key press
+    autofire: bool,
+    // Bullets that can be on the screen at once, the ship can't fire any more until one is gone
+    max_bullets: usize,
+    bullet_velocity: f32,
+    // Distance a bullet travels before vanishing, however many times it wraps around the screen
+    bullet_distance: f32,
+    // Whether the velocity of the ship is added to the bullets it fires
+    inherit_velocity: bool,
+}
+
+impl Default for Weapon {
+    fn default() -> Self {
+        Weapon {
+            cooldown: WEAPON_COOLDOWN,
+            autofire: false,
+            max_bullets: WEAPON_MAX_BULLETS,
+            bullet_velocity: BULLET_VELOCITY,
+            bullet_distance: BULLET_DISTANCE,
+            inherit_velocity: WEAPON_INHERIT_VELOCITY,
+        }
+    }
+}
+
+// Time left before the weapon of a ship can fire again
+#[derive(Component, Deref, DerefMut)]
+struct Reload(Timer);
+
+// What the player is asking the ship to do, read from the keyboard every frame and
+// applied on the next physics step
+#[derive(Component, Default)]
+struct StarshipControls {
+    thrust: bool,
+    // 1.0 to turn left, -1.0 to turn right
+    rotation: f32,
+    // Set when the fire key is pressed, until a bullet is fired
+    fire: bool,
+    // Whether the fire key is held, which keeps firing with rapid fire
+    holding_fire: bool,
+    // Set when the hyperspace key is pressed, until the ship jumps
+    hyperspace: bool,
+    // Whether the shield key is held
+    shield: bool,
+}
+
+// Number of ships the player has left, including the one currently flying
+#[derive(Resource)]
+struct Lives(u32);
+
+// Points earned by the player during the current game
+#[derive(Resource, Default)]
+struct Score(u32);
+
+// How long the current game has been played, pauses excluded
+#[derive(Resource, Default, Deref, DerefMut)]
+struct TimeSurvived(Stopwatch);
+
+// Delay between losing a ship and the next one appearing at the center
+#[derive(Resource, Deref, DerefMut)]
+struct RespawnTimer(Timer);
+
+// A freshly respawned ship can't be destroyed until this runs out, and blinks meanwhile
+#[derive(Component)]
+struct Invulnerable {
+    timer: Timer,
+    blink: Timer,
+}
+
+impl Invulnerable {
+    fn new() -> Self {
+        Invulnerable {
+            timer: Timer::from_seconds(STARSHIP_INVULNERABILITY_DURATION, TimerMode::Once),
+            blink: Timer::from_seconds(STARSHIP_BLINK_PERIOD, TimerMode::Repeating),
+        }
+    }
 }
 
 #[derive(Component)]
 struct Bullet {
-start: Vec2,
+// Distance travelled so far, which keeps growing when the bullet wraps around the screen
+travelled: f32,
+// Distance after which the bullet vanishes
+range: f32,
 }
 
 #[derive(Component)]
 struct Asteroid {
 size: AsteroidSize,
+// Which of the shared outlines it is drawn with, see `GameAssets`
+variant: usize,
+}
+
+// Outline of an asteroid, as a polygon around its center at a scale of 1.0 (the same as
+// the circle it replaces, with a radius of 0.5). The transform scales it to the asteroid size
+#[derive(Component, Clone)]
+struct AsteroidShape {
+    vertices: Vec<Vec2>,
+    // Convex hull of the vertices, used for collisions
+    hull: Vec<Vec2>,
+}
+
+impl AsteroidShape {
+    // A jagged polygon, with vertices spread around the circle at random distances from the
+    // center. Every call gives a different shape
+    fn random(settings: &AsteroidShapeSettings) -> Self {
+        let vertex_count = rand::thread_rng().gen_range(settings.min_vertices..=settings.max_vertices);
+        let angle_step = 2.0 * PI / vertex_count as f32;
+
+        let vertices = (0..vertex_count)
+            .map(|index| {
+                // Jitter the angles a bit too, so the vertices aren't evenly spaced
+                let angle = (index as f32 + (rand::random::<f32>() - 0.5) * 0.5) * angle_step;
+                let radius = 0.5 * (1.0 - settings.jaggedness * rand::random::<f32>());
+                Vec2::from_angle(angle) * radius
+            })
+            .collect::<Vec<_>>();
+        let hull = convex_hull(&vertices);
+
+        AsteroidShape { vertices, hull }
+    }
+}
+
+// How asteroid shapes are generated. It will be a resource in the app
+#[derive(Resource, Debug, Clone)]
+struct AsteroidShapeSettings {
+    min_vertices: usize,
+    max_vertices: usize,
+    // How deep the dents in the outline can be, from 0.0 (a regular polygon) to 1.0
+    jaggedness: f32,
+}
+
+impl Default for AsteroidShapeSettings {
+    fn default() -> Self {
+        AsteroidShapeSettings {
+            min_vertices: 9,
+            max_vertices: 14,
+            jaggedness: 0.35,
+        }
+    }
 }
 
 #[derive(Component)]
@@ -201,12 +510,19 @@ struct Position(Vec2);
 #[derive(Component)]
 struct Velocity(Vec2);
 
+// Position at the previous physics step, used to interpolate the rendering between steps
+#[derive(Component)]
+struct PreviousPosition(Vec2);
+
 fn create_starship_mesh() -> Mesh {
 let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
 
 mesh.insert_attribute(
     Mesh::ATTRIBUTE_POSITION,
-    vec![[0.0, 0.5, 0.0], [-0.25, -0.5, 0.0], [0.25, -0.5, 0.0]],
+    STARSHIP_VERTICES
+        .iter()
+        .map(|vertex| [vertex.x, vertex.y, 0.0])
+        .collect::<Vec<_>>(),
 );
 mesh.set_indices(Some(Indices::U32(vec![0, 1, 2])));
 mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0, 0.0, 1.0]; 3]);
@@ -218,6 +534,60 @@ mesh.insert_attribute(
 mesh
 }
 
+// The outline drawn for an asteroid: its vertices, with `detail` small dents along each edge.
+// The dents only go inwards, so the asteroid never looks bigger than it is for collisions
+fn asteroid_outline(shape: &AsteroidShape, detail: usize) -> Vec<Vec2> {
+    let vertex_count = shape.vertices.len();
+    (0..vertex_count)
+        .flat_map(|index| {
+            let start = shape.vertices[index];
+            let end = shape.vertices[(index + 1) % vertex_count];
+            std::iter::once(start).chain((1..=detail).map(move |step| {
+                let point = start.lerp(end, step as f32 / (detail + 1) as f32);
+                // Derived from the point rather than random, so the dents stay the same when
+                // the mesh is rebuilt
+                let noise = (point.dot(Vec2::new(12.9898, 78.233)).sin() * 43758.547).fract();
+                point * (1.0 - ASTEROID_DENT_DEPTH * noise.abs())
+            }))
+        })
+        .collect()
+}
+
+// The polygon is drawn as a fan of triangles around its center, which works for any shape
+// where every vertex can be seen from the center
+fn create_asteroid_mesh(shape: &AsteroidShape, detail: usize) -> Mesh {
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+
+    let outline = asteroid_outline(shape, detail);
+    let positions: Vec<[f32; 3]> = std::iter::once(Vec2::ZERO)
+        .chain(outline.iter().copied())
+        .map(|vertex| [vertex.x, vertex.y, 0.0])
+        .collect();
+    let uvs: Vec<[f32; 2]> = positions
+        .iter()
+        .map(|position| [position[0] + 0.5, 0.5 - position[1]])
+        .collect();
+    let vertex_count = outline.len() as u32;
+    let indices = (1..=vertex_count)
+        .flat_map(|index| [0, index, index % vertex_count + 1])
+        .collect();
+
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0, 0.0, 1.0]; positions.len()]);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+
+    mesh
+}
+
+// A disc of diameter 1.0
+fn create_circle_mesh(segments: usize) -> Mesh {
+    Mesh::from(shape::Circle {
+        radius: 0.5,
+        vertices: segments,
+    })
+}
+
 fn get_random_point() -> Vec2 {
 Vec2::new(
     (rand::random::<f32>() * 2.0 - 1.0) * (VIEWPORT_WIDTH as f32) / 2.0,
@@ -225,230 +595,707 @@ Vec2::new(
 )
 }
 
-fn setup_game(
-mut commands: Commands,
-mut meshes: ResMut<Assets<Mesh>>,
-mut materials: ResMut<Assets<ColorMaterial>>,
-) {
+// Mesh and material handles shared by every entity of the game, created once on startup so
+// that spawning doesn't keep adding identical assets. It will be a resource in the app
+#[derive(Resource)]
+struct GameAssets {
+    starship_mesh: Mesh2dHandle,
+    starship_material: Handle<ColorMaterial>,
+    bullet_mesh: Mesh2dHandle,
+    bullet_material: Handle<ColorMaterial>,
+    // Asteroids don't each get their own outline, they pick one of these at random. Each one
+    // has its own material, as the textured style tints them differently
+    asteroid_variants: Vec<(AsteroidShape, Mesh2dHandle, Handle<ColorMaterial>)>,
+    // The warning markers keep the shape of the ship whatever the render style
+    warning_mesh: Mesh2dHandle,
+    warning_material: Handle<ColorMaterial>,
+    shield_mesh: Mesh2dHandle,
+    shield_material: Handle<ColorMaterial>,
+    flame_mesh: Mesh2dHandle,
+    flame_material: Handle<ColorMaterial>,
+    saucer_mesh: Mesh2dHandle,
+    large_saucer_material: Handle<ColorMaterial>,
+    small_saucer_material: Handle<ColorMaterial>,
+    saucer_bullet_material: Handle<ColorMaterial>,
+    power_up_materials: HashMap<PowerUpKind, Handle<ColorMaterial>>,
+}
 
-commands.spawn(Camera2dBundle::default());
+fn setup_game_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    shape_settings: Res<AsteroidShapeSettings>,
+    quality_settings: Res<QualitySettings>,
+    render_style: Res<RenderStyle>,
+    textures: Res<SpriteTextures>,
+) {
+    let style = *render_style;
+    let asteroid_variants = (0..ASTEROID_SHAPE_VARIANTS)
+        .map(|variant| {
+            let shape = AsteroidShape::random(&shape_settings);
+            let (mesh, material) =
+                asteroid_look(style, &textures, &quality_settings, &shape, variant);
+            (shape, meshes.add(mesh).into(), materials.add(material))
+        })
+        .collect();
+    let (starship_mesh, starship_material) = starship_look(style, &textures);
+    let (saucer_mesh, large_saucer_material) = saucer_look(style, &textures, SaucerSize::Large);
+    let (_, small_saucer_material) = saucer_look(style, &textures, SaucerSize::Small);
+    let power_up_materials = PowerUpKind::ALL
+        .into_iter()
+        .map(|kind| (kind, materials.add(ColorMaterial::from(kind.color()))))
+        .collect();
 
-commands
-    .spawn(Starship {
-    rotation_angle: 0.0,
-    })
-    .insert(Position(Vec2::splat(0.0)))
-    .insert(Velocity(Vec2::splat(0.0)))
-    .insert(MaterialMesh2dBundle {
-    mesh: meshes.add(create_starship_mesh()).into(),
-    transform: Transform::default()
-        .with_scale(Vec3::splat(50.0))
-        .with_translation(Vec3::new(0.0, 0.0, 1.0)),
-    material: materials
-        .add(ColorMaterial::from(Color::rgba(1.0, 0.0, 0.0, 1.0))),
-    ..default()
+    commands.insert_resource(GameAssets {
+        starship_mesh: meshes.add(starship_mesh).into(),
+        starship_material: materials.add(starship_material),
+        bullet_mesh: meshes
+            .add(create_circle_mesh(quality_settings.circle_segments))
+            .into(),
+        bullet_material: materials.add(ColorMaterial::from(Color::rgba(1.0, 1.0, 1.0, 1.0))),
+        asteroid_variants,
+        warning_mesh: meshes.add(create_starship_mesh()).into(),
+        warning_material: materials.add(ColorMaterial::from(Color::YELLOW)),
+        shield_mesh: meshes
+            .add(create_shield_mesh(quality_settings.circle_segments))
+            .into(),
+        shield_material: materials.add(ColorMaterial::from(Color::rgba(0.3, 0.6, 1.0, 0.4))),
+        flame_mesh: meshes.add(create_flame_mesh()).into(),
+        flame_material: materials.add(ColorMaterial::from(Color::rgb(1.0, 0.6, 0.1))),
+        saucer_mesh: meshes.add(saucer_mesh).into(),
+        large_saucer_material: materials.add(large_saucer_material),
+        small_saucer_material: materials.add(small_saucer_material),
+        saucer_bullet_material: materials.add(ColorMaterial::from(Color::rgb(1.0, 0.5, 0.2))),
+        power_up_materials,
     });
+}
 
-for _ in 0..6 {
-    commands
-    .spawn(Asteroid {
-        size: AsteroidSize::Big,
-    })
-    .insert(Position(get_random_point()))
-    .insert(Velocity(get_random_point().normalize() * ASTEROID_VELOCITY))
-    .insert(MaterialMesh2dBundle {
-        mesh: meshes.add(Mesh::from(shape::Circle::default())).into(),
-        transform: Transform::default()
-        .with_translation(Vec3::new(0.0, 0.0, 2.0)),
-        material: materials
-        .add(ColorMaterial::from(Color::rgba(0.8, 0.8, 0.8, 1.0))),
-        ..default()
+fn spawn_starship(
+    commands: &mut Commands,
+    game_assets: &GameAssets,
+    stats: StarshipStats,
+    weapon: Weapon,
+) -> Entity {
+    let mesh = &game_assets.starship_mesh;
+    let material = &game_assets.starship_material;
+
+    let mut starship = commands.spawn(Starship {
+        rotation_angle: 0.0,
+        previous_rotation_angle: 0.0,
     });
+    starship
+        .insert(StarshipControls::default())
+        .insert(stats)
+        .insert(weapon)
+        .insert(Reload(Timer::default()))
+        .insert(Shield::default())
+        .insert(PowerUps::default())
+        .insert(Collider {
+            radius: bounding_radius(&STARSHIP_VERTICES) * STARSHIP_SCALE,
+        })
+        .insert(Position(Vec2::splat(0.0)))
+        .insert(PreviousPosition(Vec2::splat(0.0)))
+        .insert(Velocity(Vec2::splat(0.0)))
+        .insert(OnGameScreen)
+        .insert(MaterialMesh2dBundle {
+            mesh: mesh.clone(),
+            transform: Transform::default()
+                .with_scale(Vec3::splat(STARSHIP_SCALE))
+                .with_translation(Vec3::new(0.0, 0.0, 1.0)),
+            material: material.clone(),
+            ..default()
+        });
+    spawn_wrap_ghosts(&mut starship, mesh, material);
+    spawn_shield_ring(&mut starship, game_assets);
+    spawn_flame(&mut starship, game_assets);
+
+    starship.id()
 }
+
+// Pick one of the shared asteroid outlines at random, other than the `excluded` ones
+fn random_asteroid_variant(game_assets: &GameAssets, excluded: &[usize]) -> usize {
+    let variants: Vec<usize> = (0..game_assets.asteroid_variants.len())
+        .filter(|variant| !excluded.contains(variant))
+        .collect();
+    variants[rand::thread_rng().gen_range(0..variants.len())]
 }
 
-fn sync_translate_transform(mut query: Query<(&Position, &mut Transform)>) {
-for (position, mut transform) in &mut query {
-    transform.translation =
-    Vec3::new(position.0.x, position.0.y, transform.translation.z);
+fn spawn_asteroid(
+    commands: &mut Commands,
+    game_assets: &GameAssets,
+    size: AsteroidSize,
+    variant: usize,
+    position: Vec2,
+    velocity: Vec2,
+) -> Entity {
+    let (shape, mesh, material) = &game_assets.asteroid_variants[variant];
+    let collider = Collider {
+        radius: bounding_radius(&shape.hull) * size.scale(),
+    };
+
+    let mut asteroid = commands.spawn(Asteroid { size, variant });
+    asteroid
+        .insert(shape.clone())
+        .insert(collider)
+        .insert(Position(position))
+        .insert(PreviousPosition(position))
+        .insert(Velocity(velocity))
+        .insert(OnGameScreen)
+        .insert(MaterialMesh2dBundle {
+            mesh: mesh.clone(),
+            transform: Transform::default().with_translation(Vec3::new(0.0, 0.0, 2.0)),
+            material: material.clone(),
+            ..default()
+        });
+    spawn_wrap_ghosts(&mut asteroid, mesh, material);
+
+    asteroid.id()
 }
+
+// Replace a destroyed asteroid with two smaller ones, flying off along the direction of
+// whatever hit it. The smallest asteroids just vanish. The new asteroids don't share an outline
+// with each other or with the destroyed one. Returns the new asteroids
+#[allow(clippy::too_many_arguments)]
+fn split_asteroid(
+    commands: &mut Commands,
+    game_assets: &GameAssets,
+    particles: &mut EventWriter<EmitParticles>,
+    camera_effects: &mut EventWriter<CameraEffect>,
+    asteroid: &Asteroid,
+    position: Vec2,
+    hit_direction: Vec2,
+    speed: f32,
+) -> Vec<Entity> {
+    let size = asteroid.size;
+    particles.send(EmitParticles::asteroid_dust(size, position));
+    camera_effects.send(CameraEffect::Shake(size.trauma()));
+    if let AsteroidSize::Big = size {
+        camera_effects.send(CameraEffect::HitStop(HIT_STOP_DURATION));
+    }
+
+    let new_size = match size {
+        AsteroidSize::Big => AsteroidSize::Medium,
+        AsteroidSize::Medium => AsteroidSize::Small,
+        AsteroidSize::Small => return Vec::new(),
+    };
+
+    let mut excluded = vec![asteroid.variant];
+    split_velocities(hit_direction, speed)
+        .into_iter()
+        .map(|velocity| {
+            let variant = random_asteroid_variant(game_assets, &excluded);
+            excluded.push(variant);
+            spawn_asteroid(commands, game_assets, new_size, variant, position, velocity)
+        })
+        .collect()
 }
 
-fn sync_asteroid_scale_transform(
-mut query: Query<(&Asteroid, &mut Transform)>,
+// The asteroids themselves are spawned wave by wave, see the `wave` module
+fn setup_game(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    weapon: Res<Weapon>,
+    mut game_phase: ResMut<NextState<GamePhase>>,
 ) {
-for (asteroid, mut transform) in &mut query {
-    transform.scale = Vec3::splat(asteroid.size.scale())
+    commands.insert_resource(Lives(STARSHIP_LIVES));
+    commands.insert_resource(Score::default());
+    commands.insert_resource(TimeSurvived::default());
+    commands.remove_resource::<RespawnTimer>();
+    game_phase.set(GamePhase::Playing);
+    spawn_starship(
+        &mut commands,
+        &game_assets,
+        StarshipStats::default(),
+        weapon.clone(),
+    );
 }
+
+// How far the rendering is between the last physics step and the next one, from 0.0 to 1.0
+fn interpolation_factor(fixed_time: &FixedTime) -> f32 {
+    fixed_time.accumulated().as_secs_f32() / fixed_time.period.as_secs_f32()
 }
 
-fn sync_starship_rotation_transform(
-mut query: Query<(&Starship, &mut Transform)>,
+fn sync_translate_transform(
+    fixed_time: Res<FixedTime>,
+    mut query: Query<(&Position, &PreviousPosition, &mut Transform)>,
 ) {
-for (starship, mut transform) in &mut query {
-    transform.rotation = Quat::from_rotation_z(starship.rotation_angle);
-}
+    let factor = interpolation_factor(&fixed_time);
+    for (position, previous_position, mut transform) in &mut query {
+        let translation = previous_position.0.lerp(position.0, factor);
+        transform.translation = translation.extend(transform.translation.z);
+    }
 }
 
-fn update_position(mut query: Query<(&Velocity, &Transform, &mut Position)>) {
-for (velocity, transform, mut position) in &mut query {
-    let mut new_position = position.0 + velocity.0;
-    let half_scale = transform.scale.max_element() / 2.0;
-
-    if new_position.x > VIEWPORT_MAX_X + half_scale {
-    new_position.x = VIEWPORT_MIN_X - half_scale;
-    } else if new_position.x < VIEWPORT_MIN_X - half_scale {
-    new_position.x = VIEWPORT_MAX_X + half_scale;
+fn sync_asteroid_scale_transform(mut query: Query<(&Asteroid, &mut Transform)>) {
+    for (asteroid, mut transform) in &mut query {
+        transform.scale = Vec3::splat(asteroid.size.scale())
     }
+}
 
-    if new_position.y > VIEWPORT_MAX_Y + half_scale {
-    new_position.y = VIEWPORT_MIN_Y - half_scale;
-    } else if new_position.y < VIEWPORT_MIN_Y - half_scale {
-    new_position.y = VIEWPORT_MAX_Y + half_scale;
+fn sync_starship_rotation_transform(
+    fixed_time: Res<FixedTime>,
+    mut query: Query<(&Starship, &mut Transform)>,
+) {
+    let factor = interpolation_factor(&fixed_time);
+    for (starship, mut transform) in &mut query {
+        let rotation_angle = starship.previous_rotation_angle
+            + (starship.rotation_angle - starship.previous_rotation_angle) * factor;
+        transform.rotation = Quat::from_rotation_z(rotation_angle);
     }
+}
 
-    position.0 = new_position;
+// Remember where everything was before this physics step moves it
+fn store_previous_state(
+    mut position_query: Query<(&Position, &mut PreviousPosition)>,
+    mut starship_query: Query<&mut Starship>,
+) {
+    for (position, mut previous_position) in &mut position_query {
+        previous_position.0 = position.0;
+    }
+    for mut starship in &mut starship_query {
+        starship.previous_rotation_angle = starship.rotation_angle;
+    }
 }
+
+fn update_position(
+    fixed_time: Res<FixedTime>,
+    mut query: Query<(&Velocity, &mut Position, &mut PreviousPosition)>,
+) {
+    let delta = fixed_time.period.as_secs_f32();
+    for (velocity, mut position, mut previous_position) in &mut query {
+        let new_position = position.0 + velocity.0 * delta;
+        let wrapped_position = wrap_position(new_position);
+
+        // Move the previous position along when wrapping around an edge, so the rendering
+        // doesn't interpolate across the whole screen
+        previous_position.0 += wrapped_position - new_position;
+        position.0 = wrapped_position;
+    }
 }
 
+// Read the keyboard every frame, the physics steps act on it later. The ship doesn't respond
+// while in hyperspace
 fn keyboard_events(
-mut commands: Commands,
-mut meshes: ResMut<Assets<Mesh>>,
-mut materials: ResMut<Assets<ColorMaterial>>,
-keys: Res<Input<KeyCode>>,
-mut key_evr: EventReader<KeyboardInput>,
-mut query: Query<(&mut Starship, &Position, &mut Velocity)>,
+    keys: Res<Input<KeyCode>>,
+    mut query: Query<&mut StarshipControls, Without<InHyperspace>>,
 ) {
-for (mut starship, starship_position, mut velocity) in &mut query {
-    if keys.pressed(KeyCode::Left) {
-    starship.rotation_angle += STARSHIP_ROTATION_SPEED;
-    } else if keys.pressed(KeyCode::Right) {
-    starship.rotation_angle -= STARSHIP_ROTATION_SPEED;
+    for mut controls in &mut query {
+        controls.thrust = keys.pressed(KeyCode::Up);
+        controls.rotation = if keys.pressed(KeyCode::Left) {
+            1.0
+        } else if keys.pressed(KeyCode::Right) {
+            -1.0
+        } else {
+            0.0
+        };
+        controls.holding_fire = keys.pressed(KeyCode::Space);
+        if keys.just_pressed(KeyCode::Space) {
+            controls.fire = true;
+        }
+        controls.shield = keys.pressed(KeyCode::LShift);
+        if keys.just_pressed(KeyCode::Down) {
+            controls.hyperspace = true;
+        }
     }
+}
+
+fn steer_starship(
+    fixed_time: Res<FixedTime>,
+    mut query: Query<(
+        &mut Starship,
+        &StarshipControls,
+        &StarshipStats,
+        &mut Velocity,
+    )>,
+) {
+    let delta = fixed_time.period.as_secs_f32();
+    for (mut starship, controls, stats, mut velocity) in &mut query {
+        starship.rotation_angle += controls.rotation * stats.rotation_speed * delta;
 
-    if keys.pressed(KeyCode::Up) {
-    velocity.0 += starship.direction() * STARSHIP_ACCELERATION;
+        if controls.thrust {
+            velocity.0 += starship.direction() * stats.acceleration * delta;
 
-    if velocity.0.length() > STARSHIP_MAX_VELOCITY {
-        velocity.0 = velocity.0.normalize_or_zero() * STARSHIP_MAX_VELOCITY;
-    }
+            if velocity.0.length() > stats.max_velocity {
+                velocity.0 = velocity.0.normalize_or_zero() * stats.max_velocity;
+            }
+        }
     }
+}
 
-    for evt in key_evr.iter() {
-    if let (ButtonState::Pressed, Some(KeyCode::Space)) =
-        (evt.state, evt.key_code)
-    {
-        commands
-        .spawn(Bullet {
-            start: starship_position.0.clone(),
+fn spawn_bullet(
+    commands: &mut Commands,
+    game_assets: &GameAssets,
+    material: &Handle<ColorMaterial>,
+    position: Vec2,
+    velocity: Vec2,
+    range: f32,
+) -> Entity {
+    let mesh = &game_assets.bullet_mesh;
+
+    let mut bullet = commands.spawn(Bullet {
+        travelled: 0.0,
+        range,
+    });
+    bullet
+        .insert(Position(position))
+        .insert(PreviousPosition(position))
+        .insert(Velocity(velocity))
+        .insert(Collider {
+            radius: BULLET_SCALE / 2.0,
         })
-        .insert(Position(starship_position.0.clone()))
-        .insert(Velocity(
-            starship.direction().normalize() * BULLET_VELOCITY,
-        ))
+        .insert(OnGameScreen)
         .insert(MaterialMesh2dBundle {
-            mesh: meshes.add(Mesh::from(shape::Circle::default())).into(),
+            mesh: mesh.clone(),
             transform: Transform::default()
-            .with_scale(Vec3::splat(5.0))
-            .with_translation(starship_position.0.clone().extend(0.0)),
-            material: materials
-            .add(ColorMaterial::from(Color::rgba(1.0, 1.0, 1.0, 1.0))),
+                .with_scale(Vec3::splat(BULLET_SCALE))
+                .with_translation(position.extend(0.0)),
+            material: material.clone(),
             ..default()
         });
-    }
-    }
+    spawn_wrap_ghosts(&mut bullet, mesh, material);
+
+    bullet.id()
 }
+
+// Fire when the weapon is reloaded and there are bullets left. A key press while the weapon
+// can't fire is lost. Power-ups change how the ship fires: rapid fire boosts the weapon, triple
+// shot fires three bullets in a fan, and piercing bullets go through asteroids
+fn fire_bullets(
+    mut commands: Commands,
+    fixed_time: Res<FixedTime>,
+    game_assets: Res<GameAssets>,
+    mut query: Query<(
+        &Starship,
+        &mut StarshipControls,
+        &Weapon,
+        &mut Reload,
+        &PowerUps,
+        &Position,
+        &Velocity,
+    )>,
+    bullets_query: Query<(), (With<Bullet>, Without<SaucerBullet>)>,
+) {
+    let live_bullets = bullets_query.iter().count();
+    for (
+        starship,
+        mut controls,
+        weapon,
+        mut reload,
+        power_ups,
+        starship_position,
+        starship_velocity,
+    ) in &mut query
+    {
+        let weapon = power_ups.boost(weapon);
+        let wants_to_fire = controls.fire || (weapon.autofire && controls.holding_fire);
+        controls.fire = false;
+
+        let reloaded = reload.tick(fixed_time.period).finished();
+        let bullets_left = weapon.max_bullets.saturating_sub(live_bullets);
+        if !wants_to_fire || !reloaded || bullets_left == 0 {
+            continue;
+        }
+        reload.0 = Timer::from_seconds(weapon.cooldown, TimerMode::Once);
+
+        // The bullet straight ahead comes first, in case there aren't enough left for all
+        let angles: &[f32] = if power_ups.has(PowerUpKind::TripleShot) {
+            &[0.0, -TRIPLE_SHOT_ANGLE, TRIPLE_SHOT_ANGLE]
+        } else {
+            &[0.0]
+        };
+        let inherited_velocity = if weapon.inherit_velocity {
+            starship_velocity.0
+        } else {
+            Vec2::ZERO
+        };
+        for angle in angles.iter().take(bullets_left) {
+            let direction = Vec2::from_angle(*angle).rotate(starship.direction().normalize());
+            let bullet = spawn_bullet(
+                &mut commands,
+                &game_assets,
+                &game_assets.bullet_material,
+                starship_position.0,
+                direction * weapon.bullet_velocity + inherited_velocity,
+                weapon.bullet_distance,
+            );
+            if power_ups.has(PowerUpKind::Piercing) {
+                commands.entity(bullet).insert(Piercing::default());
+            }
+        }
+    }
 }
 
+// Bullets vanish after travelling their range. Measured from the velocity rather than from
+// where they started, a bullet wrapping around the screen doesn't look any closer or further
 fn remove_bullet(
-mut commands: Commands,
-query: Query<(Entity, &Bullet, &Position)>,
+    mut commands: Commands,
+    fixed_time: Res<FixedTime>,
+    mut query: Query<(Entity, &mut Bullet, &Velocity)>,
 ) {
-for (entity, bullet, position) in &query {
-    if (bullet.start - position.0).length() > BULLET_DISTANCE {
-    commands.entity(entity).despawn();
+    let delta = fixed_time.period.as_secs_f32();
+    for (entity, mut bullet, velocity) in &mut query {
+        bullet.travelled += velocity.0.length() * delta;
+        if bullet.travelled > bullet.range {
+            commands.entity(entity).despawn_recursive();
+        }
     }
 }
-}
 
 fn decelerate_starship(
-keys: Res<Input<KeyCode>>,
-mut query: Query<&mut Velocity, With<Starship>>,
+    fixed_time: Res<FixedTime>,
+    mut query: Query<(&StarshipControls, &mut Velocity), With<Starship>>,
 ) {
-// Only decelerate when not accelerating
-if !keys.pressed(KeyCode::Up) {
-    for mut velocity in &mut query {
-    velocity.0 *= 1.0 - STARSHIP_DECELERATION;
+    let delta = fixed_time.period.as_secs_f32();
+    for (controls, mut velocity) in &mut query {
+        // Only decelerate when not accelerating
+        if !controls.thrust {
+            velocity.0 *= (-STARSHIP_DECELERATION * delta).exp();
+        }
     }
 }
-}
 
+#[allow(clippy::too_many_arguments)]
 fn detect_starship_asteroid_collision(
-mut commands: Commands,
-starship_query: Query<(Entity, &Transform, &Position), With<Starship>>,
-asteroids_query: Query<(&Transform, &Position), With<Asteroid>>,
+    mut commands: Commands,
+    mut lives: ResMut<Lives>,
+    mut game_phase: ResMut<NextState<GamePhase>>,
+    mut particles: EventWriter<EmitParticles>,
+    mut camera_effects: EventWriter<CameraEffect>,
+    spatial_hash: Res<SpatialHash>,
+    starship_query: Query<
+        (
+            Entity,
+            &Starship,
+            &Position,
+            &Velocity,
+            &Collider,
+            Option<&Shield>,
+        ),
+        (Without<Invulnerable>, Without<InHyperspace>),
+    >,
+    mut asteroids_query: Query<
+        (&Asteroid, &AsteroidShape, &Position, &mut Velocity),
+        Without<Starship>,
+    >,
+) {
+    for (
+        starship_entity,
+        starship,
+        starship_position,
+        starship_velocity,
+        starship_collider,
+        shield,
+    ) in &starship_query
+    {
+        let starship_hull = Hull::new(
+            &STARSHIP_VERTICES,
+            starship_position.0,
+            starship.rotation_angle,
+            STARSHIP_SCALE,
+        );
+
+        for entity in spatial_hash.query(starship_position.0, starship_collider.radius) {
+            let Ok((asteroid, asteroid_shape, asteroid_position, mut asteroid_velocity)) =
+                asteroids_query.get_mut(entity)
+            else {
+                continue;
+            };
+            // Test against the copy of the asteroid closest to the ship, which may be across
+            // an edge of the screen
+            let asteroid_position =
+                starship_position.0 + wrapped_delta(starship_position.0, asteroid_position.0);
+            let asteroid_hull =
+                Hull::new(&asteroid_shape.hull, asteroid_position, 0.0, asteroid.size.scale());
+
+            if !starship_hull.intersects(&asteroid_hull) {
+                continue;
+            }
+
+            // With the shield up, the asteroid bounces off the ship, unless it is already
+            // moving away from it
+            if shield.is_some_and(|shield| shield.active) {
+                let normal = (asteroid_position - starship_position.0).normalize_or_zero();
+                let approach = (asteroid_velocity.0 - starship_velocity.0).dot(normal);
+                if approach < 0.0 {
+                    asteroid_velocity.0 -= 2.0 * approach * normal;
+                }
+                continue;
+            }
+
+            destroy_starship(
+                &mut commands,
+                &mut lives,
+                &mut game_phase,
+                &mut particles,
+                &mut camera_effects,
+                starship_entity,
+                starship_position.0,
+            );
+            // The ship is gone, no need to test it against the remaining asteroids
+            break;
+        }
+    }
+}
+
+// Blow up the ship and lose a life, then either wait for the ship to respawn or end the game
+fn destroy_starship(
+    commands: &mut Commands,
+    lives: &mut Lives,
+    game_phase: &mut NextState<GamePhase>,
+    particles: &mut EventWriter<EmitParticles>,
+    camera_effects: &mut EventWriter<CameraEffect>,
+    starship: Entity,
+    position: Vec2,
 ) {
-for (starship_entity, starship_transform, starship_position) in
-    &starship_query
-{
-    for (asteroid_transform, asteroid_position) in &asteroids_query {
-    let starship_size = starship_transform.scale.max_element();
-    let asteroid_size = asteroid_transform.scale.max_element();
-    let distance = (starship_position.0 - asteroid_position.0).length();
-
-    if distance < starship_size / 4.0 + asteroid_size / 2.0 {
-        commands.entity(starship_entity).despawn();
+    commands.entity(starship).despawn_recursive();
+    particles.send(EmitParticles::ship_debris(position));
+    camera_effects.send(CameraEffect::Shake(STARSHIP_DESTROYED_TRAUMA));
+    camera_effects.send(CameraEffect::Flash);
+    lives.0 = lives.0.saturating_sub(1);
+    if lives.0 > 0 {
+        commands.insert_resource(RespawnTimer(Timer::from_seconds(
+            STARSHIP_RESPAWN_DELAY,
+            TimerMode::Once,
+        )));
+    } else {
+        game_phase.set(GamePhase::GameOver);
+    }
+}
+
+// Freeze the game and bring up the pause menu
+fn pause_on_escape(keys: Res<Input<KeyCode>>, mut game_phase: ResMut<NextState<GamePhase>>) {
+    if keys.just_pressed(KeyCode::Escape) {
+        game_phase.set(GamePhase::Paused);
     }
+}
+
+fn tick_time_survived(time: Res<Time>, mut time_survived: ResMut<TimeSurvived>) {
+    time_survived.tick(time.delta());
+}
+
+// Tick the respawn timer, and bring back the ship at the center when finished
+fn respawn_starship(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    upgrades: Res<Upgrades>,
+    weapon: Res<Weapon>,
+    time: Res<Time>,
+    timer: Option<ResMut<RespawnTimer>>,
+) {
+    let Some(mut timer) = timer else {
+        return;
+    };
+
+    if timer.tick(time.delta()).finished() {
+        commands.remove_resource::<RespawnTimer>();
+        let (stats, weapon) = upgrades.upgrade(&weapon);
+        let starship = spawn_starship(&mut commands, &game_assets, stats, weapon);
+        commands.entity(starship).insert(Invulnerable::new());
     }
 }
+
+// Blink the ship while it is invulnerable, and make it vulnerable again once the timer runs out
+fn blink_invulnerable_starship(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Invulnerable, &mut Visibility), With<Starship>>,
+) {
+    for (entity, mut invulnerable, mut visibility) in &mut query {
+        if invulnerable.timer.tick(time.delta()).finished() {
+            *visibility = Visibility::Inherited;
+            commands.entity(entity).remove::<Invulnerable>();
+        } else if invulnerable.blink.tick(time.delta()).just_finished() {
+            *visibility = match *visibility {
+                Visibility::Hidden => Visibility::Inherited,
+                _ => Visibility::Hidden,
+            };
+        }
+    }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn detect_bullet_asteroid_collision(
-mut commands: Commands,
-mut meshes: ResMut<Assets<Mesh>>,
-mut materials: ResMut<Assets<ColorMaterial>>,
-bullets_query: Query<(Entity, &Transform, &Position), With<Bullet>>,
-asteroids_query: Query<(Entity, &Asteroid, &Transform, &Position)>,
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    mut score: ResMut<Score>,
+    wave: Res<Wave>,
+    wave_curve: Res<WaveCurve>,
+    power_up_table: Res<PowerUpTable>,
+    spatial_hash: Res<SpatialHash>,
+    mut particles: EventWriter<EmitParticles>,
+    mut camera_effects: EventWriter<CameraEffect>,
+    mut bullets_query: Query<
+        (
+            Entity,
+            &Position,
+            &Velocity,
+            &Collider,
+            Option<&mut Piercing>,
+        ),
+        (With<Bullet>, Without<SaucerBullet>),
+    >,
+    asteroids_query: Query<(&Asteroid, &AsteroidShape, &Position)>,
 ) {
-for (bullet_entity, bullet_transform, bullet_position) in &bullets_query {
-    for (asteroid_entity, asteroid, asteroid_transform, asteroid_position) in
-    &asteroids_query
+    // Asteroids already destroyed this frame, so that two bullets can't both score on them
+    let mut destroyed = Vec::new();
+
+    for (bullet_entity, bullet_position, bullet_velocity, bullet_collider, mut piercing) in
+        &mut bullets_query
     {
-    let bullet_size = bullet_transform.scale.max_element();
-    let asteroid_size = asteroid_transform.scale.max_element();
-    let distance = (bullet_position.0 - asteroid_position.0).length();
-
-    if distance < bullet_size / 2.0 + asteroid_size / 2.0 {
-        commands.entity(bullet_entity).despawn();
-        commands.entity(asteroid_entity).despawn();
-
-        let asteroid_new_size = match asteroid.size {
-        AsteroidSize::Big => Some(AsteroidSize::Medium),
-        AsteroidSize::Medium => Some(AsteroidSize::Small),
-        AsteroidSize::Small => None,
-        };
+        for asteroid_entity in spatial_hash.query(bullet_position.0, bullet_collider.radius) {
+            let ignored = piercing
+                .as_ref()
+                .is_some_and(|piercing| piercing.ignored.contains(&asteroid_entity));
+            if destroyed.contains(&asteroid_entity) || ignored {
+                continue;
+            }
+            let Ok((asteroid, asteroid_shape, asteroid_position)) =
+                asteroids_query.get(asteroid_entity)
+            else {
+                continue;
+            };
+            // Test against the copy of the asteroid closest to the bullet, which may be across
+            // an edge of the screen
+            let asteroid_hull = Hull::new(
+                &asteroid_shape.hull,
+                bullet_position.0 + wrapped_delta(bullet_position.0, asteroid_position.0),
+                0.0,
+                asteroid.size.scale(),
+            );
 
-        if let Some(asteroid_new_size) = asteroid_new_size {
-        for _ in 0..2 {
-            commands
-            .spawn(Asteroid {
-                size: asteroid_new_size,
-            })
-            .insert(Position(asteroid_position.0.clone()))
-            .insert(Velocity(
-                get_random_point().normalize() * ASTEROID_VELOCITY,
-            ))
-            .insert(MaterialMesh2dBundle {
-                mesh: meshes.add(Mesh::from(shape::Circle::default())).into(),
-                transform: Transform::default()
-                .with_translation(Vec3::new(0.0, 0.0, 2.0)),
-                material: materials
-                .add(ColorMaterial::from(Color::rgba(0.8, 0.8, 0.8, 1.0))),
-                ..default()
-            });
-        }
+            if asteroid_hull.intersects_circle(bullet_position.0, bullet_collider.radius) {
+                commands.entity(asteroid_entity).despawn_recursive();
+                destroyed.push(asteroid_entity);
+                score.0 += asteroid.size.points();
+                particles.send(EmitParticles::impact_sparks(
+                    bullet_position.0,
+                    bullet_velocity.0,
+                ));
+                let pieces = split_asteroid(
+                    &mut commands,
+                    &game_assets,
+                    &mut particles,
+                    &mut camera_effects,
+                    asteroid,
+                    asteroid_position.0,
+                    bullet_velocity.0,
+                    wave_curve.asteroid_velocity(wave.0),
+                );
+                drop_power_up(
+                    &mut commands,
+                    &game_assets,
+                    &power_up_table,
+                    asteroid_position.0,
+                );
+
+                if let Some(piercing) = piercing.as_mut() {
+                    piercing.ignored.extend(pieces);
+                    continue;
+                }
+                // The bullet is used up, no need to test it against the remaining asteroids
+                commands.entity(bullet_entity).despawn_recursive();
+                break;
+            }
         }
     }
-    }
-}
 }
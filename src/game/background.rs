@@ -0,0 +1,86 @@
+use bevy::prelude::*;
+use rand::Rng;
+use std::f32::consts::PI;
+
+use super::{get_random_point, quality::QualitySettings, OnGameScreen};
+use crate::GameState;
+
+// This plugin draws the stars in the background of the game. How many there are, and whether
+// they twinkle, depends on the display quality
+pub struct BackgroundPlugin;
+
+impl Plugin for BackgroundPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(spawn_stars.in_schedule(OnEnter(GameState::Game)))
+            .add_systems(
+                (
+                    spawn_stars.run_if(resource_changed::<QualitySettings>()),
+                    twinkle_stars,
+                )
+                    .in_set(OnUpdate(GameState::Game)),
+            );
+    }
+}
+
+// Behind everything else, but still in front of the camera's far plane
+const STAR_Z: f32 = -0.05;
+const STAR_MIN_SIZE: f32 = 1.0;
+const STAR_MAX_SIZE: f32 = 2.5;
+// Radians per second of the brightness changes of twinkling stars
+const STAR_TWINKLE_SPEED: f32 = 3.0;
+
+#[derive(Component)]
+struct Star {
+    // From 0.0 to 1.0, the alpha of the star when it doesn't twinkle
+    brightness: f32,
+    // Where the star is in its twinkling, so they don't all twinkle together
+    phase: f32,
+}
+
+// Replace the stars with as many as the display quality asks for
+fn spawn_stars(
+    mut commands: Commands,
+    quality_settings: Res<QualitySettings>,
+    stars_query: Query<Entity, With<Star>>,
+) {
+    for entity in &stars_query {
+        commands.entity(entity).despawn();
+    }
+
+    let mut rng = rand::thread_rng();
+    for _ in 0..quality_settings.star_count {
+        let brightness = rng.gen_range(0.2..0.8);
+        commands.spawn((
+            Star {
+                brightness,
+                phase: rng.gen_range(0.0..2.0 * PI),
+            },
+            OnGameScreen,
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgba(1.0, 1.0, 1.0, brightness),
+                    custom_size: Some(Vec2::splat(rng.gen_range(STAR_MIN_SIZE..STAR_MAX_SIZE))),
+                    ..default()
+                },
+                transform: Transform::from_translation(get_random_point().extend(STAR_Z)),
+                ..default()
+            },
+        ));
+    }
+}
+
+fn twinkle_stars(
+    time: Res<Time>,
+    quality_settings: Res<QualitySettings>,
+    mut query: Query<(&Star, &mut Sprite)>,
+) {
+    if !quality_settings.twinkling_stars {
+        return;
+    }
+
+    let t = time.elapsed_seconds() * STAR_TWINKLE_SPEED;
+    for (star, mut sprite) in &mut query {
+        let twinkle = 0.7 + 0.3 * (t + star.phase).sin();
+        sprite.color.set_a(star.brightness * twinkle);
+    }
+}
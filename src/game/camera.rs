@@ -0,0 +1,168 @@
+use bevy::prelude::*;
+
+use super::OnGameScreen;
+use crate::{GameState, MainCamera, ScreenEffects};
+
+// This plugin makes the camera react to what happens in the game: it shakes when things blow
+// up, the game freezes for a moment on big kills and the screen flashes white when the ship
+// is destroyed. Shaking and flashing can be turned off in the settings
+pub struct GameCameraPlugin;
+
+impl Plugin for GameCameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<CameraEffect>()
+            .add_system(camera_effects_setup.in_schedule(OnEnter(GameState::Game)))
+            .add_systems(
+                (
+                    receive_camera_effects,
+                    shake_camera.after(receive_camera_effects),
+                    hit_stop.after(receive_camera_effects),
+                    flash_screen.after(receive_camera_effects),
+                )
+                    .in_set(OnUpdate(GameState::Game)),
+            )
+            .add_system(reset_camera.in_schedule(OnExit(GameState::Game)));
+    }
+}
+
+// Offset and angle of the camera at full trauma, the shake grows with the square of the trauma
+const MAX_SHAKE_OFFSET: f32 = 12.0;
+const MAX_SHAKE_ANGLE: f32 = 0.03;
+// Trauma lost per second
+const TRAUMA_DECAY: f32 = 1.2;
+// How fast the camera moves around while shaking
+const SHAKE_FREQUENCY: f32 = 25.0;
+const FLASH_DURATION: f32 = 0.25;
+const FLASH_MAX_ALPHA: f32 = 0.6;
+
+// Sent by the game to shake the camera, freeze the game or flash the screen
+pub(super) enum CameraEffect {
+    // Adds this much trauma, from 0.0 to 1.0, to the shake
+    Shake(f32),
+    // Freezes the game for this many seconds
+    HitStop(f32),
+    Flash,
+}
+
+// State of the camera effects. It will be a resource in the app. These effects run on the real
+// time, so that they keep going during a hit-stop
+#[derive(Resource)]
+struct CameraEffects {
+    // From 0.0 (still) to 1.0 (shaking as much as it can)
+    trauma: f32,
+    hit_stop: Timer,
+    flash: Timer,
+}
+
+impl Default for CameraEffects {
+    fn default() -> Self {
+        let finished = |seconds| {
+            let mut timer = Timer::from_seconds(seconds, TimerMode::Once);
+            timer.tick(timer.duration());
+            timer
+        };
+        CameraEffects {
+            trauma: 0.0,
+            hit_stop: finished(0.0),
+            flash: finished(FLASH_DURATION),
+        }
+    }
+}
+
+// Tag component used to find the white overlay flashing over the screen
+#[derive(Component)]
+struct FlashOverlay;
+
+fn camera_effects_setup(mut commands: Commands) {
+    commands.insert_resource(CameraEffects::default());
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                ..default()
+            },
+            background_color: Color::NONE.into(),
+            // In front of the HUD and the menus
+            z_index: ZIndex::Global(100),
+            ..default()
+        },
+        FlashOverlay,
+        OnGameScreen,
+    ));
+}
+
+// Shaking and flashing are dropped when turned off in the settings, freezing is kept as it
+// doesn't move anything on the screen
+fn receive_camera_effects(
+    mut time: ResMut<Time>,
+    screen_effects: Res<ScreenEffects>,
+    mut effects: ResMut<CameraEffects>,
+    mut events: EventReader<CameraEffect>,
+) {
+    for event in events.iter() {
+        match *event {
+            CameraEffect::Shake(trauma) if *screen_effects == ScreenEffects::On => {
+                effects.trauma = (effects.trauma + trauma).min(1.0);
+            }
+            CameraEffect::Flash if *screen_effects == ScreenEffects::On => effects.flash.reset(),
+            CameraEffect::HitStop(seconds) => {
+                effects.hit_stop = Timer::from_seconds(seconds, TimerMode::Once);
+                time.pause();
+            }
+            _ => {}
+        }
+    }
+}
+
+// Move the camera around its resting place, smoothly rather than jumping each frame
+fn shake_camera(
+    time: Res<Time>,
+    screen_effects: Res<ScreenEffects>,
+    mut effects: ResMut<CameraEffects>,
+    mut query: Query<&mut Transform, With<MainCamera>>,
+) {
+    effects.trauma = (effects.trauma - TRAUMA_DECAY * time.raw_delta_seconds()).max(0.0);
+    let shake = if *screen_effects == ScreenEffects::On {
+        effects.trauma * effects.trauma
+    } else {
+        0.0
+    };
+
+    // A few sine waves at unrelated frequencies make up for a noise function
+    let t = time.raw_elapsed_seconds() * SHAKE_FREQUENCY;
+    let noise = |seed: f32| ((t + seed).sin() + (2.3 * t + 1.7 * seed).sin()) / 2.0;
+    for mut transform in &mut query {
+        transform.translation.x = MAX_SHAKE_OFFSET * shake * noise(0.0);
+        transform.translation.y = MAX_SHAKE_OFFSET * shake * noise(10.0);
+        transform.rotation = Quat::from_rotation_z(MAX_SHAKE_ANGLE * shake * noise(20.0));
+    }
+}
+
+fn hit_stop(mut time: ResMut<Time>, mut effects: ResMut<CameraEffects>) {
+    let raw_delta = time.raw_delta();
+    if time.is_paused() && effects.hit_stop.tick(raw_delta).finished() {
+        time.unpause();
+    }
+}
+
+fn flash_screen(
+    time: Res<Time>,
+    mut effects: ResMut<CameraEffects>,
+    mut query: Query<&mut BackgroundColor, With<FlashOverlay>>,
+) {
+    let fade = 1.0 - effects.flash.tick(time.raw_delta()).percent();
+    for mut background_color in &mut query {
+        background_color.0 = Color::rgba(1.0, 1.0, 1.0, FLASH_MAX_ALPHA * fade);
+    }
+}
+
+// The camera is shared with the menus, leave it as it was before the game
+fn reset_camera(mut time: ResMut<Time>, mut query: Query<&mut Transform, With<MainCamera>>) {
+    time.unpause();
+    for mut transform in &mut query {
+        transform.translation.x = 0.0;
+        transform.translation.y = 0.0;
+        transform.rotation = Quat::IDENTITY;
+    }
+}
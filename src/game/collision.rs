@@ -0,0 +1,255 @@
+use bevy::prelude::*;
+
+// Radius of a circle around the object containing its whole outline, used to find which
+// objects are close enough to be worth testing
+#[derive(Component)]
+pub(super) struct Collider {
+    pub(super) radius: f32,
+}
+
+// A convex outline placed in the world, tested against other outlines with the separating
+// axis theorem. A bounding circle around it makes most tests cheap, as far apart objects
+// are rejected before looking at any edge
+pub(super) struct Hull {
+    vertices: Vec<Vec2>,
+    center: Vec2,
+    radius: f32,
+}
+
+impl Hull {
+    // `local_vertices` must be a convex polygon around the origin, in counter-clockwise order
+    pub(super) fn new(local_vertices: &[Vec2], position: Vec2, rotation: f32, scale: f32) -> Self {
+        let rotation = Vec2::from_angle(rotation);
+        let vertices = local_vertices
+            .iter()
+            .map(|vertex| position + rotation.rotate(*vertex * scale))
+            .collect();
+
+        Hull {
+            vertices,
+            center: position,
+            radius: bounding_radius(local_vertices) * scale,
+        }
+    }
+
+    pub(super) fn intersects(&self, other: &Hull) -> bool {
+        if self.center.distance_squared(other.center) > (self.radius + other.radius).powi(2) {
+            return false;
+        }
+
+        // Two convex polygons don't touch if and only if one of their edges separates them
+        !self.edge_normals().chain(other.edge_normals()).any(|axis| {
+            let (min_a, max_a) = project(&self.vertices, axis);
+            let (min_b, max_b) = project(&other.vertices, axis);
+            max_a < min_b || max_b < min_a
+        })
+    }
+
+    pub(super) fn intersects_circle(&self, center: Vec2, radius: f32) -> bool {
+        if self.center.distance_squared(center) > (self.radius + radius).powi(2) {
+            return false;
+        }
+
+        // For a circle, the only extra axis to test is towards the closest vertex
+        let closest_vertex = self
+            .vertices
+            .iter()
+            .copied()
+            .min_by(|a, b| {
+                a.distance_squared(center)
+                    .total_cmp(&b.distance_squared(center))
+            })
+            .unwrap_or(self.center);
+        let closest_axis = (closest_vertex - center).normalize_or_zero();
+
+        !self
+            .edge_normals()
+            .chain(std::iter::once(closest_axis))
+            .any(|axis| {
+                let (min, max) = project(&self.vertices, axis);
+                let projected_center = center.dot(axis);
+                max < projected_center - radius || projected_center + radius < min
+            })
+    }
+
+    fn edge_normals(&self) -> impl Iterator<Item = Vec2> + '_ {
+        let next_vertices = self.vertices.iter().cycle().skip(1);
+        self.vertices
+            .iter()
+            .zip(next_vertices)
+            .map(|(vertex, next_vertex)| (*next_vertex - *vertex).perp().normalize_or_zero())
+    }
+}
+
+// Smallest and largest position of the vertices along an axis
+fn project(vertices: &[Vec2], axis: Vec2) -> (f32, f32) {
+    vertices.iter().map(|vertex| vertex.dot(axis)).fold(
+        (f32::INFINITY, f32::NEG_INFINITY),
+        |(min, max), projected| (min.min(projected), max.max(projected)),
+    )
+}
+
+// Radius of the smallest circle around the origin containing all the vertices
+pub(super) fn bounding_radius(vertices: &[Vec2]) -> f32 {
+    vertices
+        .iter()
+        .map(|vertex| vertex.length())
+        .fold(0.0, f32::max)
+}
+
+// Convex hull of a set of points in counter-clockwise order, using the monotone chain algorithm
+pub(super) fn convex_hull(points: &[Vec2]) -> Vec<Vec2> {
+    let mut points = points.to_vec();
+    points.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+
+    // Whether going from `a` to `b` then `c` turns left
+    let turns_left = |a: Vec2, b: Vec2, c: Vec2| (b - a).perp_dot(c - a) > 0.0;
+
+    let mut hull: Vec<Vec2> = Vec::with_capacity(points.len() + 1);
+    // Lower half from left to right, then upper half from right to left
+    for pass in [points.clone(), points.iter().rev().copied().collect()] {
+        let start = hull.len();
+        for point in pass {
+            while hull.len() >= start + 2
+                && !turns_left(hull[hull.len() - 2], hull[hull.len() - 1], point)
+            {
+                hull.pop();
+            }
+            hull.push(point);
+        }
+        // The last point of each half is the first point of the other one
+        hull.pop();
+    }
+
+    hull
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    const TRIANGLE: [Vec2; 3] = [
+        Vec2::new(0.0, 1.0),
+        Vec2::new(-1.0, -1.0),
+        Vec2::new(1.0, -1.0),
+    ];
+    const SQUARE: [Vec2; 4] = [
+        Vec2::new(-1.0, -1.0),
+        Vec2::new(1.0, -1.0),
+        Vec2::new(1.0, 1.0),
+        Vec2::new(-1.0, 1.0),
+    ];
+
+    fn triangle_at(position: Vec2, rotation: f32) -> Hull {
+        Hull::new(&TRIANGLE, position, rotation, 1.0)
+    }
+
+    #[test]
+    fn overlapping_triangles_intersect() {
+        let a = triangle_at(Vec2::ZERO, 0.0);
+        let b = triangle_at(Vec2::new(0.5, 0.5), 0.0);
+        assert!(a.intersects(&b));
+        assert!(b.intersects(&a));
+    }
+
+    #[test]
+    fn touching_triangles_intersect() {
+        // Sharing only the corner at (1, -1)
+        let a = triangle_at(Vec2::ZERO, 0.0);
+        let b = triangle_at(Vec2::new(2.0, 0.0), 0.0);
+        assert!(a.intersects(&b));
+        assert!(b.intersects(&a));
+    }
+
+    #[test]
+    fn separated_triangles_dont_intersect() {
+        let a = triangle_at(Vec2::ZERO, 0.0);
+        // Far enough for the bounding circles not to touch
+        assert!(!a.intersects(&triangle_at(Vec2::new(3.0, 0.0), 0.0)));
+        // Upside down just above the tip: the bounding circles overlap, an edge separates them
+        let b = triangle_at(Vec2::new(0.0, 2.2), PI);
+        assert!(!a.intersects(&b));
+        assert!(!b.intersects(&a));
+    }
+
+    #[test]
+    fn circle_near_a_corner() {
+        let square = Hull::new(&SQUARE, Vec2::ZERO, 0.0, 1.0);
+        // Overlapping the square along both of its axes, but out of reach of the corner
+        assert!(!square.intersects_circle(Vec2::new(1.5, 1.5), 0.6));
+        assert!(square.intersects_circle(Vec2::new(1.5, 1.5), 0.8));
+    }
+
+    #[test]
+    fn circle_near_an_edge() {
+        let square = Hull::new(&SQUARE, Vec2::ZERO, 0.0, 1.0);
+        assert!(!square.intersects_circle(Vec2::new(1.5, 0.0), 0.4));
+        assert!(square.intersects_circle(Vec2::new(1.5, 0.0), 0.6));
+        assert!(square.intersects_circle(Vec2::ZERO, 0.1));
+    }
+
+    #[test]
+    fn hull_is_placed_in_the_world() {
+        // A quarter turn and a scale of 2 take the tip of the triangle to (-2, 0), from (10, 0)
+        let hull = Hull::new(&TRIANGLE, Vec2::new(10.0, 0.0), PI / 2.0, 2.0);
+        assert!(hull.intersects_circle(Vec2::new(8.0, 0.0), 0.01));
+        assert!(!hull.intersects_circle(Vec2::new(10.0, 2.5), 0.1));
+    }
+
+    #[test]
+    fn convex_hull_drops_inner_and_collinear_points() {
+        let points = [
+            Vec2::new(1.0, 1.0),
+            Vec2::new(0.0, 0.0),
+            Vec2::new(1.0, 0.0),
+            Vec2::new(2.0, 0.0),
+            Vec2::new(2.0, 2.0),
+            Vec2::new(0.0, 2.0),
+            Vec2::new(2.0, 1.0),
+        ];
+        assert_eq!(
+            convex_hull(&points),
+            vec![
+                Vec2::new(0.0, 0.0),
+                Vec2::new(2.0, 0.0),
+                Vec2::new(2.0, 2.0),
+                Vec2::new(0.0, 2.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn convex_hull_of_points_on_a_line() {
+        let points = [
+            Vec2::new(1.0, 1.0),
+            Vec2::new(2.0, 2.0),
+            Vec2::new(0.0, 0.0),
+        ];
+        assert_eq!(
+            convex_hull(&points),
+            vec![Vec2::new(0.0, 0.0), Vec2::new(2.0, 2.0)]
+        );
+    }
+
+    #[test]
+    fn convex_hull_of_fewer_than_three_points() {
+        assert_eq!(convex_hull(&[]), Vec::<Vec2>::new());
+        assert_eq!(
+            convex_hull(&[Vec2::new(1.0, 1.0)]),
+            vec![Vec2::new(1.0, 1.0)]
+        );
+        assert_eq!(
+            convex_hull(&[
+                Vec2::new(1.0, 1.0),
+                Vec2::new(0.0, 0.0),
+                Vec2::new(1.0, 1.0)
+            ]),
+            vec![Vec2::new(0.0, 0.0), Vec2::new(1.0, 1.0)]
+        );
+    }
+}
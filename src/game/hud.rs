@@ -0,0 +1,255 @@
+use bevy::prelude::*;
+
+use super::{
+    powerup::{PowerUpKind, PowerUps},
+    shield::Shield,
+    Lives, OnGameScreen, Score, Starship, Wave,
+};
+use crate::{GameState, TEXT_COLOR};
+
+// This plugin displays the score, the current wave, the shield energy and the remaining lives
+// on top of the game, and the power-ups the ship is under in the bottom left corner
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(hud_setup.in_schedule(OnEnter(GameState::Game)))
+            .add_systems(
+                (
+                    update_score_text,
+                    update_lives_text,
+                    update_wave_text,
+                    update_shield_meter,
+                    update_power_up_icons,
+                )
+                    .in_set(OnUpdate(GameState::Game)),
+            );
+    }
+}
+
+// Tag component used to find the text displaying the score
+#[derive(Component)]
+struct ScoreText;
+
+// Tag component used to find the text displaying the remaining lives
+#[derive(Component)]
+struct LivesText;
+
+// Tag component used to find the text displaying the current wave
+#[derive(Component)]
+struct WaveText;
+
+// Tag component used to find the bar filled with the shield energy
+#[derive(Component)]
+struct ShieldMeter;
+
+// Tag component used to find the line displaying a power-up, hidden while it isn't active
+#[derive(Component)]
+struct PowerUpIcon(PowerUpKind);
+
+// Tag component used to find the text displaying the time left on a power-up
+#[derive(Component)]
+struct PowerUpTimeText(PowerUpKind);
+
+const POWER_UP_ICON_SIZE: f32 = 16.0;
+const SHIELD_METER_WIDTH: f32 = 150.0;
+const SHIELD_METER_HEIGHT: f32 = 20.0;
+const SHIELD_METER_BACKGROUND: Color = Color::rgb(0.15, 0.15, 0.15);
+const SHIELD_METER_COLOR: Color = Color::rgb(0.3, 0.6, 1.0);
+
+fn hud_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let label_style = TextStyle {
+        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+        font_size: 30.0,
+        color: TEXT_COLOR,
+    };
+    let value_style = TextStyle {
+        font: asset_server.load("fonts/FiraMono-Medium.ttf"),
+        font_size: 30.0,
+        color: TEXT_COLOR,
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Auto),
+                    position_type: PositionType::Absolute,
+                    // Spread the texts along the top of the screen
+                    justify_content: JustifyContent::SpaceBetween,
+                    padding: UiRect::all(Val::Px(10.0)),
+                    ..default()
+                },
+                ..default()
+            },
+            OnGameScreen,
+        ))
+        .with_children(|parent| {
+            // Each text is a label followed by a value that is kept up to date
+            let text = |label: &str| {
+                TextBundle::from_sections([
+                    TextSection::new(label, label_style.clone()),
+                    TextSection::from_style(value_style.clone()),
+                ])
+            };
+            parent.spawn((text("Score "), ScoreText));
+            parent.spawn((text("Wave "), WaveText));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section("Shield ", label_style.clone()));
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                size: Size::new(
+                                    Val::Px(SHIELD_METER_WIDTH),
+                                    Val::Px(SHIELD_METER_HEIGHT),
+                                ),
+                                ..default()
+                            },
+                            background_color: SHIELD_METER_BACKGROUND.into(),
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            parent.spawn((
+                                NodeBundle {
+                                    style: Style {
+                                        size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                                        ..default()
+                                    },
+                                    background_color: SHIELD_METER_COLOR.into(),
+                                    ..default()
+                                },
+                                ShieldMeter,
+                            ));
+                        });
+                });
+            parent.spawn((text("Lives "), LivesText));
+        });
+
+    // One line for each power-up with a lasting effect: a square of its colour, its name and
+    // the time left
+    let power_up_style = TextStyle {
+        font_size: 20.0,
+        ..value_style
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        left: Val::Px(10.0),
+                        bottom: Val::Px(10.0),
+                        ..default()
+                    },
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                ..default()
+            },
+            OnGameScreen,
+        ))
+        .with_children(|parent| {
+            for kind in PowerUpKind::ALL {
+                if kind == PowerUpKind::ExtraLife {
+                    continue;
+                }
+                parent
+                    .spawn((
+                        NodeBundle {
+                            style: Style {
+                                display: Display::None,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            ..default()
+                        },
+                        PowerUpIcon(kind),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(NodeBundle {
+                            style: Style {
+                                size: Size::new(
+                                    Val::Px(POWER_UP_ICON_SIZE),
+                                    Val::Px(POWER_UP_ICON_SIZE),
+                                ),
+                                margin: UiRect::right(Val::Px(8.0)),
+                                ..default()
+                            },
+                            background_color: kind.color().into(),
+                            ..default()
+                        });
+                        parent.spawn((
+                            TextBundle::from_sections([
+                                TextSection::new(
+                                    format!("{} ", kind.label()),
+                                    power_up_style.clone(),
+                                ),
+                                TextSection::from_style(power_up_style.clone()),
+                            ]),
+                            PowerUpTimeText(kind),
+                        ));
+                    });
+            }
+        });
+}
+
+fn update_score_text(score: Res<Score>, mut query: Query<&mut Text, With<ScoreText>>) {
+    for mut text in &mut query {
+        text.sections[1].value = format!("{:>6}", score.0);
+    }
+}
+
+fn update_lives_text(lives: Res<Lives>, mut query: Query<&mut Text, With<LivesText>>) {
+    for mut text in &mut query {
+        text.sections[1].value = lives.0.to_string();
+    }
+}
+
+fn update_wave_text(wave: Res<Wave>, mut query: Query<&mut Text, With<WaveText>>) {
+    for mut text in &mut query {
+        text.sections[1].value = wave.0.to_string();
+    }
+}
+
+// The meter is empty while there is no ship
+fn update_shield_meter(
+    shield_query: Query<&Shield, With<Starship>>,
+    mut meter_query: Query<&mut Style, With<ShieldMeter>>,
+) {
+    let energy = shield_query
+        .iter()
+        .next()
+        .map_or(0.0, |shield| shield.energy);
+    for mut style in &mut meter_query {
+        style.size.width = Val::Percent(energy * 100.0);
+    }
+}
+
+fn update_power_up_icons(
+    power_ups_query: Query<&PowerUps, With<Starship>>,
+    mut icon_query: Query<(&PowerUpIcon, &mut Style)>,
+    mut text_query: Query<(&PowerUpTimeText, &mut Text)>,
+) {
+    let power_ups = power_ups_query.iter().next();
+    let remaining = |kind| power_ups.and_then(|power_ups| power_ups.remaining(kind));
+
+    for (icon, mut style) in &mut icon_query {
+        style.display = match remaining(icon.0) {
+            Some(_) => Display::Flex,
+            None => Display::None,
+        };
+    }
+    for (time_text, mut text) in &mut text_query {
+        if let Some(remaining) = remaining(time_text.0) {
+            text.sections[1].value = format!("{remaining:.1}s");
+        }
+    }
+}
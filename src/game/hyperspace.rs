@@ -0,0 +1,166 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use super::{
+    camera::CameraEffect,
+    destroy_starship,
+    particle::EmitParticles,
+    spawn::{safe_random_point, SPAWN_CLEARANCE},
+    Asteroid, Lives, Position, PreviousPosition, Starship, StarshipControls, Velocity,
+    STARSHIP_SCALE,
+};
+use crate::GamePhase;
+
+// This plugin lets the ship escape by jumping to a random place on the screen. The ship
+// shrinks away, comes back somewhere else, and may blow up on re-entry
+pub struct HyperspacePlugin;
+
+impl Plugin for HyperspacePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HyperspaceSettings>().add_systems(
+            (
+                enter_hyperspace,
+                travel_hyperspace,
+                tick_hyperspace_cooldown,
+            )
+                .in_set(OnUpdate(GamePhase::Playing)),
+        );
+    }
+}
+
+// How often the ship can jump, how long a jump takes and how risky it is. It will be a
+// resource in the app
+#[derive(Resource, Debug, Clone)]
+pub(super) struct HyperspaceSettings {
+    // Seconds before the ship can jump again, counted from the start of a jump
+    pub(super) cooldown: f32,
+    // Seconds the jump lasts, from the ship disappearing to it being back
+    pub(super) duration: f32,
+    // Probability of the ship blowing up on re-entry, from 0.0 to 1.0
+    pub(super) failure_chance: f64,
+}
+
+impl Default for HyperspaceSettings {
+    fn default() -> Self {
+        HyperspaceSettings {
+            cooldown: 4.0,
+            duration: 0.6,
+            failure_chance: 0.1,
+        }
+    }
+}
+
+// Present on the ship during a jump. It can't be controlled nor hit while in hyperspace
+#[derive(Component)]
+pub(super) struct InHyperspace {
+    timer: Timer,
+    // Whether the ship has been moved to its destination yet, halfway through the jump
+    arrived: bool,
+}
+
+// Present on the ship until it can jump again
+#[derive(Component, Deref, DerefMut)]
+struct HyperspaceCooldown(Timer);
+
+fn enter_hyperspace(
+    mut commands: Commands,
+    settings: Res<HyperspaceSettings>,
+    mut query: Query<
+        (
+            Entity,
+            &mut StarshipControls,
+            &mut Velocity,
+            Option<&HyperspaceCooldown>,
+        ),
+        (With<Starship>, Without<InHyperspace>),
+    >,
+) {
+    for (entity, mut controls, mut velocity, cooldown) in &mut query {
+        // A jump asked for during the cooldown is dropped, not delayed
+        if !std::mem::take(&mut controls.hyperspace) || cooldown.is_some() {
+            continue;
+        }
+
+        *controls = StarshipControls::default();
+        velocity.0 = Vec2::ZERO;
+        commands.entity(entity).insert((
+            InHyperspace {
+                timer: Timer::from_seconds(settings.duration, TimerMode::Once),
+                arrived: false,
+            },
+            HyperspaceCooldown(Timer::from_seconds(settings.cooldown, TimerMode::Once)),
+        ));
+    }
+}
+
+// Shrink the ship away during the first half of the jump and grow it back at its destination
+// during the second half. The destination is picked when the ship gets there, so it is away
+// from where the asteroids are at that time
+#[allow(clippy::too_many_arguments)]
+fn travel_hyperspace(
+    mut commands: Commands,
+    time: Res<Time>,
+    settings: Res<HyperspaceSettings>,
+    mut lives: ResMut<Lives>,
+    mut game_phase: ResMut<NextState<GamePhase>>,
+    mut particles: EventWriter<EmitParticles>,
+    mut camera_effects: EventWriter<CameraEffect>,
+    mut starship_query: Query<
+        (
+            Entity,
+            &mut InHyperspace,
+            &mut Position,
+            &mut PreviousPosition,
+            &mut Transform,
+        ),
+        With<Starship>,
+    >,
+    asteroids_query: Query<&Position, (With<Asteroid>, Without<Starship>)>,
+) {
+    for (entity, mut hyperspace, mut position, mut previous_position, mut transform) in
+        &mut starship_query
+    {
+        let progress = hyperspace.timer.tick(time.delta()).percent();
+
+        if progress >= 0.5 && !hyperspace.arrived {
+            let asteroids: Vec<Vec2> = asteroids_query.iter().map(|position| position.0).collect();
+            position.0 = safe_random_point(&asteroids, SPAWN_CLEARANCE);
+            previous_position.0 = position.0;
+            transform.translation = position.0.extend(transform.translation.z);
+            hyperspace.arrived = true;
+        }
+
+        if !hyperspace.timer.finished() {
+            // A zero scale couldn't be inverted to place the wrap ghosts
+            let size = (2.0 * progress - 1.0).abs().max(f32::EPSILON);
+            transform.scale = Vec3::splat(STARSHIP_SCALE * size);
+            continue;
+        }
+
+        transform.scale = Vec3::splat(STARSHIP_SCALE);
+        commands.entity(entity).remove::<InHyperspace>();
+        if rand::thread_rng().gen_bool(settings.failure_chance.clamp(0.0, 1.0)) {
+            destroy_starship(
+                &mut commands,
+                &mut lives,
+                &mut game_phase,
+                &mut particles,
+                &mut camera_effects,
+                entity,
+                position.0,
+            );
+        }
+    }
+}
+
+fn tick_hyperspace_cooldown(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut HyperspaceCooldown)>,
+) {
+    for (entity, mut cooldown) in &mut query {
+        if cooldown.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<HyperspaceCooldown>();
+        }
+    }
+}
@@ -0,0 +1,223 @@
+use bevy::prelude::*;
+use rand::Rng;
+use std::{f32::consts::PI, ops::Range};
+
+use super::{quality::QualitySettings, wrap::wrap_position, AsteroidSize, OnGameScreen};
+use crate::GamePhase;
+
+// This plugin runs the particles: dust from destroyed asteroids, debris from the ship, sparks
+// where bullets hit and the exhaust of the ship. Particles are only for show, they don't
+// collide with anything and move every frame instead of on the physics steps
+pub struct ParticlePlugin;
+
+impl Plugin for ParticlePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<EmitParticles>().add_systems(
+            (emit_particles, update_particles.after(emit_particles))
+                .distributive_run_if(particles_running),
+        );
+    }
+}
+
+// In front of the bullets, behind the ship, the saucers and the asteroids
+const PARTICLE_Z: f32 = 0.5;
+// Seconds the exhaust lasts at most, with the full trail
+const EXHAUST_LIFETIME: f32 = 1.0;
+
+// How the particles of a burst look and move
+#[derive(Debug, Clone)]
+struct Emitter {
+    // Particles emitted at the highest display quality, fewer at lower ones
+    count: usize,
+    // Length and width of a particle, which points in the direction it moves
+    size: Vec2,
+    speed: Range<f32>,
+    // Particles fly within this angle on either side of the direction of the burst
+    spread: f32,
+    lifetime: Range<f32>,
+    // Most radians per second a particle turns by, either way
+    spin: f32,
+    // Rate at which particles slow down, the velocity decays as exp(-drag * t)
+    drag: f32,
+    start_color: Color,
+    end_color: Color,
+}
+
+// Sent to emit a burst of particles somewhere on the screen
+pub(super) struct EmitParticles {
+    emitter: Emitter,
+    position: Vec2,
+    direction: Vec2,
+}
+
+impl EmitParticles {
+    // A cloud of dust all around, the bigger the asteroid the more dust
+    pub(super) fn asteroid_dust(size: AsteroidSize, position: Vec2) -> Self {
+        let scale = size.scale();
+        EmitParticles {
+            emitter: Emitter {
+                count: (scale / 4.0) as usize,
+                size: Vec2::splat(3.0),
+                speed: 20.0..40.0 + scale,
+                spread: PI,
+                lifetime: 0.4..1.0,
+                spin: 0.0,
+                drag: 1.5,
+                start_color: Color::rgba(0.8, 0.75, 0.7, 0.9),
+                end_color: Color::rgba(0.4, 0.4, 0.4, 0.0),
+            },
+            position,
+            direction: Vec2::X,
+        }
+    }
+
+    // Pieces of the hull tumbling away from where the ship blew up
+    pub(super) fn ship_debris(position: Vec2) -> Self {
+        EmitParticles {
+            emitter: Emitter {
+                count: 12,
+                size: Vec2::new(12.0, 2.0),
+                speed: 30.0..120.0,
+                spread: PI,
+                lifetime: 1.0..2.0,
+                spin: 6.0,
+                drag: 0.8,
+                start_color: Color::WHITE,
+                end_color: Color::rgba(1.0, 1.0, 1.0, 0.0),
+            },
+            position,
+            direction: Vec2::X,
+        }
+    }
+
+    // A few short sparks bouncing back from a bullet hitting something, `direction` being the
+    // way the bullet was going
+    pub(super) fn impact_sparks(position: Vec2, direction: Vec2) -> Self {
+        EmitParticles {
+            emitter: Emitter {
+                count: 6,
+                size: Vec2::new(5.0, 1.5),
+                speed: 120.0..260.0,
+                spread: 0.6,
+                lifetime: 0.1..0.3,
+                spin: 0.0,
+                drag: 4.0,
+                start_color: Color::rgb(1.0, 0.95, 0.6),
+                end_color: Color::rgba(1.0, 0.4, 0.1, 0.0),
+            },
+            position,
+            direction: -direction,
+        }
+    }
+
+    // A single puff of exhaust blown out in `direction`, fading from the colour of the flame.
+    // The longer the trail, the longer the puff lasts
+    pub(super) fn exhaust(position: Vec2, direction: Vec2, trail_length: f32) -> Self {
+        let lifetime = EXHAUST_LIFETIME * trail_length.max(0.1);
+        EmitParticles {
+            emitter: Emitter {
+                count: 1,
+                size: Vec2::splat(4.0),
+                speed: 40.0..90.0,
+                spread: 0.35,
+                lifetime: lifetime / 2.0..lifetime,
+                spin: 0.0,
+                drag: 2.0,
+                start_color: Color::rgba(1.0, 0.7, 0.2, 0.8),
+                end_color: Color::rgba(0.5, 0.5, 0.5, 0.0),
+            },
+            position,
+            direction,
+        }
+    }
+}
+
+#[derive(Component)]
+struct Particle {
+    velocity: Vec2,
+    spin: f32,
+    drag: f32,
+    lifetime: Timer,
+    start_color: Color,
+    end_color: Color,
+}
+
+// Particles keep going after the ship is destroyed for good, but stop with the rest of the game
+// when it is paused
+fn particles_running(game_phase: Res<State<GamePhase>>) -> bool {
+    matches!(game_phase.0, GamePhase::Playing | GamePhase::GameOver)
+}
+
+// Bursts are cut short once the budget is used up
+fn emit_particles(
+    mut commands: Commands,
+    quality_settings: Res<QualitySettings>,
+    mut events: EventReader<EmitParticles>,
+    particles_query: Query<(), With<Particle>>,
+) {
+    let max_particles = quality_settings.max_particles;
+    let density = quality_settings.particle_density;
+    let mut live_particles = particles_query.iter().count();
+    let mut rng = rand::thread_rng();
+
+    for event in events.iter() {
+        let emitter = &event.emitter;
+        let count = ((emitter.count as f32 * density).round() as usize)
+            .min(max_particles.saturating_sub(live_particles));
+        live_particles += count;
+
+        let direction = event.direction.y.atan2(event.direction.x);
+        for _ in 0..count {
+            let angle = direction + rng.gen_range(-emitter.spread..=emitter.spread);
+            commands.spawn((
+                Particle {
+                    velocity: Vec2::from_angle(angle) * rng.gen_range(emitter.speed.clone()),
+                    spin: rng.gen_range(-emitter.spin..=emitter.spin),
+                    drag: emitter.drag,
+                    lifetime: Timer::from_seconds(
+                        rng.gen_range(emitter.lifetime.clone()),
+                        TimerMode::Once,
+                    ),
+                    start_color: emitter.start_color,
+                    end_color: emitter.end_color,
+                },
+                OnGameScreen,
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: emitter.start_color,
+                        custom_size: Some(emitter.size),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(event.position.extend(PARTICLE_Z))
+                        .with_rotation(Quat::from_rotation_z(angle)),
+                    ..default()
+                },
+            ));
+        }
+    }
+}
+
+// Move the particles and blend their colour from start to end over their lifetime
+fn update_particles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Particle, &mut Transform, &mut Sprite)>,
+) {
+    let delta = time.delta_seconds();
+    for (entity, mut particle, mut transform, mut sprite) in &mut query {
+        if particle.lifetime.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        let decay = (-particle.drag * delta).exp();
+        particle.velocity *= decay;
+        let position = wrap_position(transform.translation.truncate() + particle.velocity * delta);
+        transform.translation = position.extend(transform.translation.z);
+        transform.rotate_z(particle.spin * delta);
+
+        let start = Vec4::from(particle.start_color.as_rgba_f32());
+        let end = Vec4::from(particle.end_color.as_rgba_f32());
+        sprite.color = Color::from(start.lerp(end, particle.lifetime.percent()));
+    }
+}
@@ -0,0 +1,256 @@
+use bevy::{prelude::*, sprite::MaterialMesh2dBundle, utils::HashMap};
+use rand::{distributions::WeightedIndex, prelude::Distribution, Rng};
+
+use super::{
+    collision::Collider, hyperspace::InHyperspace, spatial_hash::SpatialHash,
+    wrap::wrapped_distance, GameAssets, Lives, OnGameScreen, Position, Starship, Weapon,
+};
+use crate::GamePhase;
+
+// This plugin handles the power-ups dropped by destroyed asteroids: they vanish after a few
+// seconds if the ship doesn't pick them up, and their effect on the ship only lasts a while.
+// Picking them up is checked with the other collisions, see `collect_power_ups`
+pub struct PowerUpPlugin;
+
+impl Plugin for PowerUpPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PowerUpTable>().add_systems(
+            (expire_power_ups, tick_active_power_ups).in_set(OnUpdate(GamePhase::Playing)),
+        );
+    }
+}
+
+const POWER_UP_SCALE: f32 = 16.0;
+// A power-up starts blinking when it is about to vanish
+const POWER_UP_WARNING_DURATION: f32 = 2.0;
+const POWER_UP_BLINK_PERIOD: f32 = 0.15;
+// Angle between the bullets of a triple shot
+pub(super) const TRIPLE_SHOT_ANGLE: f32 = 0.2;
+// Rapid fire turns on autofire, shortens the cooldown by this factor and allows more bullets
+const RAPID_FIRE_COOLDOWN_FACTOR: f32 = 0.4;
+const RAPID_FIRE_EXTRA_BULLETS: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(super) enum PowerUpKind {
+    RapidFire,
+    TripleShot,
+    Shield,
+    ExtraLife,
+    Piercing,
+}
+
+impl PowerUpKind {
+    pub(super) const ALL: [PowerUpKind; 5] = [
+        PowerUpKind::RapidFire,
+        PowerUpKind::TripleShot,
+        PowerUpKind::Shield,
+        PowerUpKind::ExtraLife,
+        PowerUpKind::Piercing,
+    ];
+
+    pub(super) fn color(&self) -> Color {
+        match self {
+            PowerUpKind::RapidFire => Color::rgb(1.0, 0.8, 0.2),
+            PowerUpKind::TripleShot => Color::rgb(1.0, 0.4, 0.8),
+            PowerUpKind::Shield => Color::rgb(0.3, 0.6, 1.0),
+            PowerUpKind::ExtraLife => Color::rgb(0.3, 1.0, 0.4),
+            PowerUpKind::Piercing => Color::rgb(0.7, 0.4, 1.0),
+        }
+    }
+
+    pub(super) fn label(&self) -> &'static str {
+        match self {
+            PowerUpKind::RapidFire => "Rapid fire",
+            PowerUpKind::TripleShot => "Triple shot",
+            PowerUpKind::Shield => "Shield",
+            PowerUpKind::ExtraLife => "Extra life",
+            PowerUpKind::Piercing => "Piercing",
+        }
+    }
+}
+
+// One line of the drop table
+#[derive(Debug, Clone)]
+pub(super) struct PowerUpDrop {
+    pub(super) kind: PowerUpKind,
+    // How likely this power-up is compared to the others
+    pub(super) weight: u32,
+    // Seconds the effect lasts once picked up, unused for instant ones like an extra life
+    pub(super) duration: f32,
+}
+
+// How often destroyed asteroids drop a power-up, for how long it stays, and the weight and
+// duration of each kind. It will be a resource in the app
+#[derive(Resource, Debug, Clone)]
+pub(super) struct PowerUpTable {
+    // Probability of a destroyed asteroid dropping anything, from 0.0 to 1.0
+    pub(super) drop_chance: f64,
+    // Seconds a dropped power-up stays on the screen
+    pub(super) lifetime: f32,
+    pub(super) drops: Vec<PowerUpDrop>,
+}
+
+impl Default for PowerUpTable {
+    fn default() -> Self {
+        let drop = |kind, weight, duration| PowerUpDrop {
+            kind,
+            weight,
+            duration,
+        };
+        PowerUpTable {
+            drop_chance: 0.1,
+            lifetime: 6.0,
+            drops: vec![
+                drop(PowerUpKind::RapidFire, 3, 8.0),
+                drop(PowerUpKind::TripleShot, 3, 8.0),
+                drop(PowerUpKind::Shield, 2, 5.0),
+                drop(PowerUpKind::ExtraLife, 1, 0.0),
+                drop(PowerUpKind::Piercing, 2, 6.0),
+            ],
+        }
+    }
+}
+
+// A power-up waiting on the screen to be picked up
+#[derive(Component)]
+pub(super) struct PowerUp {
+    kind: PowerUpKind,
+    duration: f32,
+    lifetime: Timer,
+    blink: Timer,
+}
+
+// Timed effects the ship is currently under
+#[derive(Component, Default)]
+pub(super) struct PowerUps {
+    active: HashMap<PowerUpKind, Timer>,
+}
+
+impl PowerUps {
+    pub(super) fn has(&self, kind: PowerUpKind) -> bool {
+        self.active.contains_key(&kind)
+    }
+
+    // Seconds left before the effect wears off, if it is active
+    pub(super) fn remaining(&self, kind: PowerUpKind) -> Option<f32> {
+        self.active.get(&kind).map(|timer| timer.remaining_secs())
+    }
+
+    // The weapon of the ship as modified by the active power-ups
+    pub(super) fn boost(&self, weapon: &Weapon) -> Weapon {
+        let mut weapon = weapon.clone();
+        if self.has(PowerUpKind::RapidFire) {
+            weapon.autofire = true;
+            weapon.cooldown *= RAPID_FIRE_COOLDOWN_FACTOR;
+            weapon.max_bullets += RAPID_FIRE_EXTRA_BULLETS;
+        }
+
+        weapon
+    }
+}
+
+// Bullets fired with piercing go through asteroids instead of being used up. They ignore the
+// pieces of the asteroids they split, which would otherwise be destroyed right away
+#[derive(Component, Default)]
+pub(super) struct Piercing {
+    pub(super) ignored: Vec<Entity>,
+}
+
+// Maybe leave a power-up where an asteroid was destroyed, picked from the drop table
+pub(super) fn drop_power_up(
+    commands: &mut Commands,
+    game_assets: &GameAssets,
+    table: &PowerUpTable,
+    position: Vec2,
+) {
+    let mut rng = rand::thread_rng();
+    if !rng.gen_bool(table.drop_chance.clamp(0.0, 1.0)) {
+        return;
+    }
+    let Ok(weights) = WeightedIndex::new(table.drops.iter().map(|drop| drop.weight)) else {
+        return;
+    };
+    let drop = &table.drops[weights.sample(&mut rng)];
+
+    commands.spawn((
+        PowerUp {
+            kind: drop.kind,
+            duration: drop.duration,
+            lifetime: Timer::from_seconds(table.lifetime, TimerMode::Once),
+            blink: Timer::from_seconds(POWER_UP_BLINK_PERIOD, TimerMode::Repeating),
+        },
+        Position(position),
+        Collider {
+            radius: POWER_UP_SCALE / 2.0,
+        },
+        OnGameScreen,
+        MaterialMesh2dBundle {
+            mesh: game_assets.bullet_mesh.clone(),
+            transform: Transform::from_translation(position.extend(2.5))
+                .with_scale(Vec3::splat(POWER_UP_SCALE)),
+            material: game_assets.power_up_materials[&drop.kind].clone(),
+            ..default()
+        },
+    ));
+}
+
+// Remove the power-ups left on the screen for too long, blinking just before
+fn expire_power_ups(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut PowerUp, &mut Visibility)>,
+) {
+    for (entity, mut power_up, mut visibility) in &mut query {
+        if power_up.lifetime.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        } else if power_up.lifetime.remaining_secs() < POWER_UP_WARNING_DURATION
+            && power_up.blink.tick(time.delta()).just_finished()
+        {
+            *visibility = match *visibility {
+                Visibility::Hidden => Visibility::Inherited,
+                _ => Visibility::Hidden,
+            };
+        }
+    }
+}
+
+// Picking up a power-up again restarts its effect. A ship in hyperspace can't pick any up
+pub(super) fn collect_power_ups(
+    mut commands: Commands,
+    spatial_hash: Res<SpatialHash>,
+    mut lives: ResMut<Lives>,
+    power_ups_query: Query<(&PowerUp, &Position, &Collider)>,
+    mut starship_query: Query<
+        (&mut PowerUps, &Position, &Collider),
+        (With<Starship>, Without<InHyperspace>),
+    >,
+) {
+    for (mut power_ups, starship_position, starship_collider) in &mut starship_query {
+        for entity in spatial_hash.query(starship_position.0, starship_collider.radius) {
+            let Ok((power_up, position, collider)) = power_ups_query.get(entity) else {
+                continue;
+            };
+            let distance = wrapped_distance(position.0, starship_position.0);
+            if distance > collider.radius + starship_collider.radius {
+                continue;
+            }
+
+            commands.entity(entity).despawn_recursive();
+            match power_up.kind {
+                PowerUpKind::ExtraLife => lives.0 += 1,
+                kind => {
+                    let timer = Timer::from_seconds(power_up.duration, TimerMode::Once);
+                    power_ups.active.insert(kind, timer);
+                }
+            }
+        }
+    }
+}
+
+fn tick_active_power_ups(time: Res<Time>, mut query: Query<&mut PowerUps>) {
+    for mut power_ups in &mut query {
+        power_ups
+            .active
+            .retain(|_, timer| !timer.tick(time.delta()).finished());
+    }
+}
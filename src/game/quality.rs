@@ -0,0 +1,115 @@
+use bevy::prelude::*;
+
+use super::{create_circle_mesh, shield::create_shield_mesh, GameAssets};
+use crate::DisplayQuality;
+
+// This plugin applies the display quality picked in the settings. It can change at any time
+// from the settings menu, even during a game, and everything it drives is updated right away
+pub struct QualityPlugin;
+
+impl Plugin for QualityPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<QualitySettings>()
+            .add_system(apply_display_quality);
+    }
+}
+
+// What the display quality changes. None of it changes how the game plays: collisions use the
+// same shapes at any quality. It will be a resource in the app, following `DisplayQuality`
+#[derive(Resource, Clone)]
+pub(super) struct QualitySettings {
+    // Smoothing of the edges, every GPU supports 4 samples but not necessarily more
+    pub(super) msaa: Msaa,
+    // Segments of the circles drawn for the bullets, the power-ups and the shield
+    pub(super) circle_segments: usize,
+    // Dents added along each edge of the asteroid outlines
+    pub(super) asteroid_detail: usize,
+    // Most particles alive at once, and the share of each burst actually emitted
+    pub(super) max_particles: usize,
+    pub(super) particle_density: f32,
+    // Seconds between two puffs of exhaust, there is no trail at all without it
+    pub(super) exhaust_period: Option<f32>,
+    // How long the exhaust trail lasts, relative to its full length
+    pub(super) trail_length: f32,
+    // Stars in the background, and whether they twinkle
+    pub(super) star_count: usize,
+    pub(super) twinkling_stars: bool,
+}
+
+impl QualitySettings {
+    pub(super) fn new(quality: DisplayQuality) -> Self {
+        match quality {
+            DisplayQuality::Low => QualitySettings {
+                msaa: Msaa::Off,
+                circle_segments: 12,
+                asteroid_detail: 0,
+                max_particles: 150,
+                particle_density: 0.35,
+                exhaust_period: None,
+                trail_length: 0.0,
+                star_count: 0,
+                twinkling_stars: false,
+            },
+            DisplayQuality::Medium => QualitySettings {
+                msaa: Msaa::Sample4,
+                circle_segments: 24,
+                asteroid_detail: 1,
+                max_particles: 400,
+                particle_density: 0.7,
+                exhaust_period: Some(0.05),
+                trail_length: 0.6,
+                star_count: 80,
+                twinkling_stars: false,
+            },
+            DisplayQuality::High => QualitySettings {
+                msaa: Msaa::Sample4,
+                circle_segments: 48,
+                asteroid_detail: 3,
+                max_particles: 1000,
+                particle_density: 1.0,
+                exhaust_period: Some(0.02),
+                trail_length: 1.0,
+                star_count: 200,
+                twinkling_stars: true,
+            },
+        }
+    }
+}
+
+impl FromWorld for QualitySettings {
+    fn from_world(world: &mut World) -> Self {
+        QualitySettings::new(*world.resource::<DisplayQuality>())
+    }
+}
+
+// The meshes are shared by every entity using them, replacing them updates everything on the
+// screen at once. The asteroids depend on the render style too, they are rebuilt along with it
+fn apply_display_quality(
+    quality: Res<DisplayQuality>,
+    mut settings: ResMut<QualitySettings>,
+    mut msaa: ResMut<Msaa>,
+    game_assets: Option<Res<GameAssets>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    if !quality.is_changed() {
+        return;
+    }
+
+    *settings = QualitySettings::new(*quality);
+    *msaa = settings.msaa;
+    if let Some(game_assets) = game_assets {
+        let mut replace = |handle: &Handle<Mesh>, mesh: Mesh| {
+            if let Some(old_mesh) = meshes.get_mut(handle) {
+                *old_mesh = mesh;
+            }
+        };
+        replace(
+            &game_assets.bullet_mesh.0,
+            create_circle_mesh(settings.circle_segments),
+        );
+        replace(
+            &game_assets.shield_mesh.0,
+            create_shield_mesh(settings.circle_segments),
+        );
+    }
+}
@@ -0,0 +1,388 @@
+use bevy::{prelude::*, sprite::MaterialMesh2dBundle};
+use rand::Rng;
+use std::f32::consts::PI;
+
+use super::{
+    camera::CameraEffect,
+    collision::{Collider, Hull},
+    destroy_starship,
+    hyperspace::InHyperspace,
+    particle::EmitParticles,
+    shield::Shield,
+    spatial_hash::SpatialHash,
+    spawn_bullet, split_asteroid,
+    wave::{Wave, WaveCurve},
+    wrap::{spawn_wrap_ghosts, wrapped_delta, wrapped_distance},
+    Asteroid, AsteroidShape, Bullet, GameAssets, Invulnerable, Lives, OnGameScreen, Position,
+    PreviousPosition, Score, Starship, Velocity, BULLET_DISTANCE, BULLET_VELOCITY, STARSHIP_SCALE,
+    STARSHIP_VERTICES, VIEWPORT_HEIGHT, VIEWPORT_MIN_X, VIEWPORT_WIDTH,
+};
+use crate::{GamePhase, GameState};
+
+// This plugin sends a flying saucer across the screen every now and then. Saucers move on
+// the physics steps like everything else, see the systems below added to the physics chain
+pub struct SaucerPlugin;
+
+impl Plugin for SaucerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(saucer_setup.in_schedule(OnEnter(GameState::Game)))
+            .add_system(spawn_saucers.in_set(OnUpdate(GamePhase::Playing)));
+    }
+}
+
+// Seconds between a saucer leaving and the next one coming in
+const SAUCER_MIN_INTERVAL: f32 = 10.0;
+const SAUCER_MAX_INTERVAL: f32 = 20.0;
+// Seconds between two changes of vertical direction
+const SAUCER_COURSE_PERIOD: f32 = 1.0;
+// Vertical speed when wandering up or down, relative to the horizontal speed
+const SAUCER_WANDER_SPEED: f32 = 0.5;
+// Probability of the saucer being a small one in the first wave, and how much it grows by
+// with each following wave
+const SMALL_SAUCER_CHANCE: f32 = 0.2;
+const SMALL_SAUCER_CHANCE_PER_WAVE: f32 = 0.1;
+const SMALL_SAUCER_MAX_CHANCE: f32 = 0.8;
+// Small saucers aim at the ship, missing by up to this angle in the first wave. The angle is
+// multiplied by the decay for each following wave
+const SMALL_SAUCER_MAX_SPREAD: f32 = PI / 6.0;
+const SMALL_SAUCER_SPREAD_DECAY: f32 = 0.8;
+const SMALL_SAUCER_MIN_SPREAD: f32 = PI / 90.0;
+// Camera shake added when a saucer is destroyed, from 0.0 to 1.0
+const SAUCER_DESTROYED_TRAUMA: f32 = 0.4;
+
+#[derive(Debug, Clone, Copy)]
+pub(super) enum SaucerSize {
+    Large,
+    Small,
+}
+
+impl SaucerSize {
+    fn scale(&self) -> f32 {
+        match self {
+            SaucerSize::Large => 60.0,
+            SaucerSize::Small => 36.0,
+        }
+    }
+
+    fn points(&self) -> u32 {
+        match self {
+            SaucerSize::Large => 200,
+            SaucerSize::Small => 1000,
+        }
+    }
+
+    fn speed(&self) -> f32 {
+        match self {
+            SaucerSize::Large => 120.0,
+            SaucerSize::Small => 180.0,
+        }
+    }
+
+    // Seconds between two shots
+    fn fire_period(&self) -> f32 {
+        match self {
+            SaucerSize::Large => 1.5,
+            SaucerSize::Small => 1.2,
+        }
+    }
+}
+
+#[derive(Component)]
+pub(super) struct Saucer {
+    size: SaucerSize,
+    // The saucer leaves once it has gone across the whole screen
+    crossing: Timer,
+    course: Timer,
+    fire: Timer,
+}
+
+// Tag component used to tag the bullets fired by saucers, which only hurt the ship
+#[derive(Component)]
+pub(super) struct SaucerBullet;
+
+// Delay before the next saucer comes in, only ticking while there is no saucer
+#[derive(Resource, Deref, DerefMut)]
+struct SaucerTimer(Timer);
+
+impl SaucerTimer {
+    fn random() -> Self {
+        let interval = rand::thread_rng().gen_range(SAUCER_MIN_INTERVAL..SAUCER_MAX_INTERVAL);
+        SaucerTimer(Timer::from_seconds(interval, TimerMode::Once))
+    }
+}
+
+fn saucer_setup(mut commands: Commands) {
+    commands.insert_resource(SaucerTimer::random());
+}
+
+fn spawn_saucers(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    time: Res<Time>,
+    wave: Res<Wave>,
+    mut saucer_timer: ResMut<SaucerTimer>,
+    saucers_query: Query<(), With<Saucer>>,
+) {
+    if !saucers_query.is_empty() || !saucer_timer.tick(time.delta()).finished() {
+        return;
+    }
+    *saucer_timer = SaucerTimer::random();
+
+    let mut rng = rand::thread_rng();
+    let small_chance = (SMALL_SAUCER_CHANCE
+        + SMALL_SAUCER_CHANCE_PER_WAVE * wave.0.saturating_sub(1) as f32)
+        .min(SMALL_SAUCER_MAX_CHANCE);
+    let size = if rng.gen_bool(small_chance as f64) {
+        SaucerSize::Small
+    } else {
+        SaucerSize::Large
+    };
+
+    // Come in from the left or right edge, and go across the screen
+    let direction = if rng.gen_bool(0.5) { 1.0 } else { -1.0 };
+    let half_height = VIEWPORT_HEIGHT as f32 / 2.0;
+    let position = Vec2::new(VIEWPORT_MIN_X, rng.gen_range(-half_height..half_height));
+    let mesh = &game_assets.saucer_mesh;
+    let material = match size {
+        SaucerSize::Large => &game_assets.large_saucer_material,
+        SaucerSize::Small => &game_assets.small_saucer_material,
+    };
+
+    let mut saucer = commands.spawn(Saucer {
+        size,
+        crossing: Timer::from_seconds(VIEWPORT_WIDTH as f32 / size.speed(), TimerMode::Once),
+        course: Timer::from_seconds(SAUCER_COURSE_PERIOD, TimerMode::Repeating),
+        fire: Timer::from_seconds(size.fire_period(), TimerMode::Repeating),
+    });
+    saucer
+        .insert(Collider {
+            radius: size.scale() * 0.4,
+        })
+        .insert(Position(position))
+        .insert(PreviousPosition(position))
+        .insert(Velocity(Vec2::new(direction * size.speed(), 0.0)))
+        .insert(OnGameScreen)
+        .insert(MaterialMesh2dBundle {
+            mesh: mesh.clone(),
+            transform: Transform::default()
+                .with_scale(Vec3::splat(size.scale()))
+                .with_translation(position.extend(1.5)),
+            material: material.clone(),
+            ..default()
+        });
+    spawn_wrap_ghosts(&mut saucer, mesh, material);
+}
+
+// Wander up and down while going across, and leave at the other side
+pub(super) fn steer_saucers(
+    mut commands: Commands,
+    fixed_time: Res<FixedTime>,
+    mut query: Query<(Entity, &mut Saucer, &mut Velocity)>,
+) {
+    let mut rng = rand::thread_rng();
+    for (entity, mut saucer, mut velocity) in &mut query {
+        if saucer.crossing.tick(fixed_time.period).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        if saucer.course.tick(fixed_time.period).just_finished() {
+            let wander = [-1.0, 0.0, 1.0][rng.gen_range(0..3)];
+            velocity.0.y = wander * SAUCER_WANDER_SPEED * saucer.size.speed();
+        }
+    }
+}
+
+// Large saucers fire in random directions, small ones aim at the ship, better and better
+// with each wave
+pub(super) fn fire_saucers(
+    mut commands: Commands,
+    fixed_time: Res<FixedTime>,
+    game_assets: Res<GameAssets>,
+    wave: Res<Wave>,
+    mut saucers_query: Query<(&mut Saucer, &Position)>,
+    starship_query: Query<&Position, With<Starship>>,
+) {
+    let mut rng = rand::thread_rng();
+    for (mut saucer, saucer_position) in &mut saucers_query {
+        if !saucer.fire.tick(fixed_time.period).just_finished() {
+            continue;
+        }
+
+        // No direction to aim at when the ship is right on the saucer
+        let target = starship_query
+            .iter()
+            .map(|starship| wrapped_delta(saucer_position.0, starship.0))
+            .min_by(|a, b| a.length_squared().total_cmp(&b.length_squared()))
+            .and_then(Vec2::try_normalize);
+        let angle = match (saucer.size, target) {
+            (SaucerSize::Small, Some(target)) => {
+                let spread = (SMALL_SAUCER_MAX_SPREAD
+                    * SMALL_SAUCER_SPREAD_DECAY.powi(wave.0.saturating_sub(1) as i32))
+                .max(SMALL_SAUCER_MIN_SPREAD);
+                Vec2::X.angle_between(target) + rng.gen_range(-spread..spread)
+            }
+            _ => rng.gen_range(0.0..2.0 * PI),
+        };
+
+        let bullet = spawn_bullet(
+            &mut commands,
+            &game_assets,
+            &game_assets.saucer_bullet_material,
+            saucer_position.0,
+            Vec2::from_angle(angle) * BULLET_VELOCITY,
+            BULLET_DISTANCE,
+        );
+        commands.entity(bullet).insert(SaucerBullet);
+    }
+}
+
+// A saucer is destroyed by the player bullets, the asteroids and the ship. Only the player
+// bullets and the ship score. Asteroids hit by a saucer split as if shot
+#[allow(clippy::too_many_arguments)]
+pub(super) fn detect_saucer_collision(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    mut score: ResMut<Score>,
+    mut lives: ResMut<Lives>,
+    mut game_phase: ResMut<NextState<GamePhase>>,
+    wave: Res<Wave>,
+    wave_curve: Res<WaveCurve>,
+    spatial_hash: Res<SpatialHash>,
+    mut particles: EventWriter<EmitParticles>,
+    mut camera_effects: EventWriter<CameraEffect>,
+    saucers_query: Query<(Entity, &Saucer, &Position, &Velocity, &Collider)>,
+    bullets_query: Query<(&Position, &Velocity, &Collider), (With<Bullet>, Without<SaucerBullet>)>,
+    asteroids_query: Query<(&Asteroid, &AsteroidShape, &Position)>,
+    starship_query: Query<
+        (&Starship, &Position, Option<&Shield>),
+        (Without<Invulnerable>, Without<InHyperspace>),
+    >,
+) {
+    for (saucer_entity, saucer, saucer_position, saucer_velocity, saucer_collider) in &saucers_query
+    {
+        for entity in spatial_hash.query(saucer_position.0, saucer_collider.radius) {
+            if let Ok((bullet_position, bullet_velocity, bullet_collider)) =
+                bullets_query.get(entity)
+            {
+                let distance = wrapped_distance(saucer_position.0, bullet_position.0);
+                if distance > saucer_collider.radius + bullet_collider.radius {
+                    continue;
+                }
+                commands.entity(entity).despawn_recursive();
+                particles.send(EmitParticles::impact_sparks(
+                    bullet_position.0,
+                    bullet_velocity.0,
+                ));
+                score.0 += saucer.size.points();
+            } else if let Ok((asteroid, asteroid_shape, asteroid_position)) =
+                asteroids_query.get(entity)
+            {
+                let asteroid_hull = Hull::new(
+                    &asteroid_shape.hull,
+                    saucer_position.0 + wrapped_delta(saucer_position.0, asteroid_position.0),
+                    0.0,
+                    asteroid.size.scale(),
+                );
+                if !asteroid_hull.intersects_circle(saucer_position.0, saucer_collider.radius) {
+                    continue;
+                }
+                commands.entity(entity).despawn_recursive();
+                split_asteroid(
+                    &mut commands,
+                    &game_assets,
+                    &mut particles,
+                    &mut camera_effects,
+                    asteroid,
+                    asteroid_position.0,
+                    saucer_velocity.0,
+                    wave_curve.asteroid_velocity(wave.0),
+                );
+            } else if let Ok((starship, starship_position, shield)) = starship_query.get(entity) {
+                let starship_hull = Hull::new(
+                    &STARSHIP_VERTICES,
+                    saucer_position.0 + wrapped_delta(saucer_position.0, starship_position.0),
+                    starship.rotation_angle,
+                    STARSHIP_SCALE,
+                );
+                if !starship_hull.intersects_circle(saucer_position.0, saucer_collider.radius) {
+                    continue;
+                }
+                // The shield protects the ship, but not the saucer
+                if !shield.is_some_and(|shield| shield.active) {
+                    destroy_starship(
+                        &mut commands,
+                        &mut lives,
+                        &mut game_phase,
+                        &mut particles,
+                        &mut camera_effects,
+                        entity,
+                        starship_position.0,
+                    );
+                }
+                score.0 += saucer.size.points();
+            } else {
+                continue;
+            }
+
+            commands.entity(saucer_entity).despawn_recursive();
+            camera_effects.send(CameraEffect::Shake(SAUCER_DESTROYED_TRAUMA));
+            // The saucer is gone, no need to test it against anything else
+            break;
+        }
+    }
+}
+
+// The bullets of the saucers are stopped by the shield, but destroy the ship otherwise
+#[allow(clippy::too_many_arguments)]
+pub(super) fn detect_saucer_bullet_collision(
+    mut commands: Commands,
+    mut lives: ResMut<Lives>,
+    mut game_phase: ResMut<NextState<GamePhase>>,
+    spatial_hash: Res<SpatialHash>,
+    mut particles: EventWriter<EmitParticles>,
+    mut camera_effects: EventWriter<CameraEffect>,
+    bullets_query: Query<(Entity, &Position, &Collider), With<SaucerBullet>>,
+    starship_query: Query<
+        (&Starship, &Position, Option<&Shield>),
+        (Without<Invulnerable>, Without<InHyperspace>),
+    >,
+) {
+    // Ships already destroyed this frame, so that two bullets can't both take a life
+    let mut destroyed = Vec::new();
+
+    for (bullet_entity, bullet_position, bullet_collider) in &bullets_query {
+        for entity in spatial_hash.query(bullet_position.0, bullet_collider.radius) {
+            if destroyed.contains(&entity) {
+                continue;
+            }
+            let Ok((starship, starship_position, shield)) = starship_query.get(entity) else {
+                continue;
+            };
+            let starship_hull = Hull::new(
+                &STARSHIP_VERTICES,
+                bullet_position.0 + wrapped_delta(bullet_position.0, starship_position.0),
+                starship.rotation_angle,
+                STARSHIP_SCALE,
+            );
+            if !starship_hull.intersects_circle(bullet_position.0, bullet_collider.radius) {
+                continue;
+            }
+
+            commands.entity(bullet_entity).despawn_recursive();
+            if !shield.is_some_and(|shield| shield.active) {
+                destroy_starship(
+                    &mut commands,
+                    &mut lives,
+                    &mut game_phase,
+                    &mut particles,
+                    &mut camera_effects,
+                    entity,
+                    starship_position.0,
+                );
+                destroyed.push(entity);
+            }
+            break;
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use bevy::{
+    ecs::system::EntityCommands,
+    prelude::*,
+    render::{mesh::Indices, render_resource::PrimitiveTopology},
+    sprite::MaterialMesh2dBundle,
+};
+use std::f32::consts::PI;
+
+use super::{
+    powerup::{PowerUpKind, PowerUps},
+    GameAssets, StarshipControls,
+};
+use crate::GamePhase;
+
+// This plugin draws the energy shield of the ship. The shield itself is updated with the
+// physics, as it changes what happens when the ship hits an asteroid
+pub struct ShieldPlugin;
+
+impl Plugin for ShieldPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ShieldSettings>()
+            .add_system(show_shield_rings.in_set(OnUpdate(GamePhase::Playing)));
+    }
+}
+
+// Inner and outer radius of the ring drawn around the ship, relative to the ship size
+const SHIELD_INNER_RADIUS: f32 = 0.7;
+const SHIELD_OUTER_RADIUS: f32 = 0.8;
+
+// How fast the shield energy drains while it is up and recharges while it is down, in full
+// meters per second. It will be a resource in the app
+#[derive(Resource, Debug, Clone)]
+pub(super) struct ShieldSettings {
+    pub(super) drain_rate: f32,
+    pub(super) recharge_rate: f32,
+    // Once emptied, the shield stays down until the energy is back to this level
+    pub(super) restart_energy: f32,
+}
+
+impl Default for ShieldSettings {
+    fn default() -> Self {
+        ShieldSettings {
+            drain_rate: 0.5,
+            recharge_rate: 0.1,
+            restart_energy: 0.2,
+        }
+    }
+}
+
+// Energy shield of the ship, up while the shield key is held and there is energy left.
+// Asteroids bounce off the ship while it is up
+#[derive(Component)]
+pub(super) struct Shield {
+    // From 0.0 (empty) to 1.0 (full)
+    pub(super) energy: f32,
+    pub(super) active: bool,
+    // Set when the energy runs out, until it has recharged enough to bring the shield back up
+    pub(super) depleted: bool,
+}
+
+impl Default for Shield {
+    fn default() -> Self {
+        Shield {
+            energy: 1.0,
+            active: false,
+            depleted: false,
+        }
+    }
+}
+
+// Tag component used to tag the ring drawn around a ship while its shield is up
+#[derive(Component)]
+struct ShieldRing;
+
+pub(super) fn create_shield_mesh(segments: usize) -> Mesh {
+    let mut positions = Vec::with_capacity(2 * segments);
+    let mut indices = Vec::with_capacity(6 * segments);
+    let segments = segments as u32;
+    for segment in 0..segments {
+        let direction = Vec2::from_angle(2.0 * PI * segment as f32 / segments as f32);
+        for radius in [SHIELD_INNER_RADIUS, SHIELD_OUTER_RADIUS] {
+            let vertex = direction * radius;
+            positions.push([vertex.x, vertex.y, 0.0]);
+        }
+
+        // Two triangles between this segment and the next one, the last one closes the ring
+        let (inner, outer) = (2 * segment, 2 * segment + 1);
+        let (next_inner, next_outer) = (
+            2 * ((segment + 1) % segments),
+            2 * ((segment + 1) % segments) + 1,
+        );
+        indices.extend([inner, next_inner, outer, outer, next_inner, next_outer]);
+    }
+
+    let vertex_count = positions.len();
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0, 0.0, 1.0]; vertex_count]);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0, 0.0]; vertex_count]);
+
+    mesh
+}
+
+// Add the ring as a child of a ship, hidden until the shield goes up
+pub(super) fn spawn_shield_ring(entity: &mut EntityCommands, game_assets: &GameAssets) {
+    entity.with_children(|parent| {
+        parent.spawn((
+            MaterialMesh2dBundle {
+                mesh: game_assets.shield_mesh.clone(),
+                material: game_assets.shield_material.clone(),
+                // Slightly in front of the ship
+                transform: Transform::from_xyz(0.0, 0.0, 0.01),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            ShieldRing,
+        ));
+    });
+}
+
+// Drain the energy while the shield is up, recharge it while it is down. An empty shield
+// stays down for a while rather than flickering on every bit of recharge. The shield power-up
+// keeps it up for free
+pub(super) fn update_shield(
+    fixed_time: Res<FixedTime>,
+    settings: Res<ShieldSettings>,
+    mut query: Query<(&StarshipControls, &mut Shield, Option<&PowerUps>)>,
+) {
+    let delta = fixed_time.period.as_secs_f32();
+    for (controls, mut shield, power_ups) in &mut query {
+        if power_ups.is_some_and(|power_ups| power_ups.has(PowerUpKind::Shield)) {
+            shield.active = true;
+            continue;
+        }
+
+        shield.active = controls.shield && !shield.depleted;
+        if shield.active {
+            shield.energy = (shield.energy - settings.drain_rate * delta).max(0.0);
+            shield.depleted = shield.energy == 0.0;
+        } else {
+            shield.energy = (shield.energy + settings.recharge_rate * delta).min(1.0);
+            shield.depleted &= shield.energy < settings.restart_energy;
+        }
+    }
+}
+
+fn show_shield_rings(
+    shield_query: Query<&Shield>,
+    mut ring_query: Query<(&Parent, &mut Visibility), With<ShieldRing>>,
+) {
+    for (parent, mut visibility) in &mut ring_query {
+        let active = shield_query
+            .get(parent.get())
+            .is_ok_and(|shield| shield.active);
+        *visibility = if active {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
@@ -0,0 +1,295 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use super::{OnGameScreen, Score, Starship, StarshipStats, Weapon};
+use crate::{
+    despawn_screen,
+    menu::{button_system, NORMAL_BUTTON},
+    GamePhase, GameState, TEXT_COLOR,
+};
+
+// This plugin shows the upgrade shop between two waves. Upgrades are paid with credits, which
+// are the points scored so far minus what was already spent, and last until the end of the game
+pub struct ShopPlugin;
+
+impl Plugin for ShopPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(upgrades_setup.in_schedule(OnEnter(GameState::Game)))
+            .add_systems((
+                shop_setup.in_schedule(OnEnter(GamePhase::Shop)),
+                despawn_screen::<OnShopScreen>.in_schedule(OnExit(GamePhase::Shop)),
+            ))
+            .add_systems(
+                (shop_action, button_system, update_shop_texts).in_set(OnUpdate(GamePhase::Shop)),
+            );
+    }
+}
+
+const MAX_UPGRADE_LEVEL: u32 = 5;
+// Price of the first level of an upgrade, each following level costs this much more
+const UPGRADE_BASE_COST: u32 = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Upgrade {
+    Thrust,
+    TopSpeed,
+    TurnRate,
+    BulletSpeed,
+    BulletRange,
+    FireRate,
+}
+
+impl Upgrade {
+    const ALL: [Upgrade; 6] = [
+        Upgrade::Thrust,
+        Upgrade::TopSpeed,
+        Upgrade::TurnRate,
+        Upgrade::BulletSpeed,
+        Upgrade::BulletRange,
+        Upgrade::FireRate,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Upgrade::Thrust => "Thrust",
+            Upgrade::TopSpeed => "Top speed",
+            Upgrade::TurnRate => "Turn rate",
+            Upgrade::BulletSpeed => "Bullet speed",
+            Upgrade::BulletRange => "Bullet range",
+            Upgrade::FireRate => "Fire rate",
+        }
+    }
+
+    // Improve the ship by one level of this upgrade
+    fn apply(&self, stats: &mut StarshipStats, weapon: &mut Weapon) {
+        match self {
+            Upgrade::Thrust => stats.acceleration *= 1.15,
+            Upgrade::TopSpeed => stats.max_velocity *= 1.1,
+            Upgrade::TurnRate => stats.rotation_speed *= 1.1,
+            Upgrade::BulletSpeed => weapon.bullet_velocity *= 1.15,
+            Upgrade::BulletRange => weapon.bullet_distance *= 1.15,
+            Upgrade::FireRate => weapon.cooldown *= 0.85,
+        }
+    }
+}
+
+// Upgrades bought during the current game. It will be a resource in the app
+#[derive(Resource, Default)]
+pub(super) struct Upgrades {
+    levels: HashMap<Upgrade, u32>,
+    spent: u32,
+}
+
+impl Upgrades {
+    fn level(&self, upgrade: Upgrade) -> u32 {
+        self.levels.get(&upgrade).copied().unwrap_or(0)
+    }
+
+    // Price of the next level, if there is one
+    fn cost(&self, upgrade: Upgrade) -> Option<u32> {
+        let level = self.level(upgrade);
+        (level < MAX_UPGRADE_LEVEL).then_some(UPGRADE_BASE_COST * (level + 1))
+    }
+
+    fn credits(&self, score: &Score) -> u32 {
+        score.0.saturating_sub(self.spent)
+    }
+
+    // Stats and weapon of a ship with every upgrade bought so far, starting from `weapon`
+    pub(super) fn upgrade(&self, weapon: &Weapon) -> (StarshipStats, Weapon) {
+        let mut stats = StarshipStats::default();
+        let mut weapon = weapon.clone();
+        for (upgrade, level) in &self.levels {
+            for _ in 0..*level {
+                upgrade.apply(&mut stats, &mut weapon);
+            }
+        }
+
+        (stats, weapon)
+    }
+}
+
+// Tag component used to tag entities added on the shop screen
+#[derive(Component)]
+struct OnShopScreen;
+
+// All actions that can be triggered from a button click in the shop
+#[derive(Component)]
+enum ShopButtonAction {
+    Buy(Upgrade),
+    Continue,
+}
+
+// Tag component used to find the text displaying the credits left
+#[derive(Component)]
+struct CreditsText;
+
+// Tag component used to find the text displaying the level and price of an upgrade
+#[derive(Component)]
+struct UpgradeText(Upgrade);
+
+fn upgrades_setup(mut commands: Commands) {
+    commands.insert_resource(Upgrades::default());
+}
+
+fn shop_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+    let button_style = Style {
+        size: Size::new(Val::Px(500.0), Val::Px(50.0)),
+        margin: UiRect::all(Val::Px(8.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let button_text_style = TextStyle {
+        font: font.clone(),
+        font_size: 30.0,
+        color: TEXT_COLOR,
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            OnShopScreen,
+            OnGameScreen,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        padding: UiRect::all(Val::Px(20.0)),
+                        ..default()
+                    },
+                    background_color: Color::MIDNIGHT_BLUE.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(
+                        TextBundle::from_section(
+                            "Upgrades",
+                            TextStyle {
+                                font: font.clone(),
+                                font_size: 60.0,
+                                color: TEXT_COLOR,
+                            },
+                        )
+                        .with_style(Style {
+                            margin: UiRect::all(Val::Px(20.0)),
+                            ..default()
+                        }),
+                    );
+                    parent.spawn((
+                        TextBundle::from_sections([
+                            TextSection::new("Credits ", button_text_style.clone()),
+                            TextSection::from_style(button_text_style.clone()),
+                        ])
+                        .with_style(Style {
+                            margin: UiRect::bottom(Val::Px(20.0)),
+                            ..default()
+                        }),
+                        CreditsText,
+                    ));
+
+                    for upgrade in Upgrade::ALL {
+                        parent
+                            .spawn((
+                                ButtonBundle {
+                                    style: button_style.clone(),
+                                    background_color: NORMAL_BUTTON.into(),
+                                    ..default()
+                                },
+                                ShopButtonAction::Buy(upgrade),
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn((
+                                    TextBundle::from_section("", button_text_style.clone()),
+                                    UpgradeText(upgrade),
+                                ));
+                            });
+                    }
+
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    margin: UiRect::top(Val::Px(30.0)),
+                                    ..button_style.clone()
+                                },
+                                background_color: NORMAL_BUTTON.into(),
+                                ..default()
+                            },
+                            ShopButtonAction::Continue,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(
+                                "Next Wave",
+                                button_text_style.clone(),
+                            ));
+                        });
+                });
+        });
+}
+
+// Buying an upgrade improves the current ship right away, and every ship spawned after it
+fn shop_action(
+    interaction_query: Query<
+        (&Interaction, &ShopButtonAction),
+        (Changed<Interaction>, With<Button>),
+    >,
+    score: Res<Score>,
+    mut upgrades: ResMut<Upgrades>,
+    weapon: Res<Weapon>,
+    mut game_phase: ResMut<NextState<GamePhase>>,
+    mut starship_query: Query<(&mut StarshipStats, &mut Weapon), With<Starship>>,
+) {
+    for (interaction, action) in &interaction_query {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        match action {
+            ShopButtonAction::Buy(upgrade) => {
+                let Some(cost) = upgrades.cost(*upgrade) else {
+                    continue;
+                };
+                if cost > upgrades.credits(&score) {
+                    continue;
+                }
+                upgrades.spent += cost;
+                *upgrades.levels.entry(*upgrade).or_insert(0) += 1;
+                for (mut stats, mut starship_weapon) in &mut starship_query {
+                    (*stats, *starship_weapon) = upgrades.upgrade(&weapon);
+                }
+            }
+            ShopButtonAction::Continue => game_phase.set(GamePhase::Playing),
+        }
+    }
+}
+
+fn update_shop_texts(
+    score: Res<Score>,
+    upgrades: Res<Upgrades>,
+    mut credits_query: Query<&mut Text, (With<CreditsText>, Without<UpgradeText>)>,
+    mut upgrades_query: Query<(&UpgradeText, &mut Text)>,
+) {
+    let credits = upgrades.credits(&score);
+    for mut text in &mut credits_query {
+        text.sections[1].value = credits.to_string();
+    }
+    for (upgrade_text, mut text) in &mut upgrades_query {
+        let upgrade = upgrade_text.0;
+        let level = upgrades.level(upgrade);
+        text.sections[0].value = match upgrades.cost(upgrade) {
+            Some(cost) => format!("{}  {level}/{MAX_UPGRADE_LEVEL}  ({cost})", upgrade.label()),
+            None => format!("{}  {level}/{MAX_UPGRADE_LEVEL}  (max)", upgrade.label()),
+        };
+    }
+}
@@ -0,0 +1,138 @@
+use bevy::prelude::*;
+
+use super::{
+    collision::Collider, Position, VIEWPORT_HEIGHT, VIEWPORT_MIN_X, VIEWPORT_MIN_Y, VIEWPORT_WIDTH,
+};
+
+// The playfield is cut in a grid of cells that divides it exactly, so that wrapping a cell
+// index around is the same as wrapping a position around the screen
+const GRID_COLUMNS: i32 = 10;
+const GRID_ROWS: i32 = 6;
+const CELL_WIDTH: f32 = VIEWPORT_WIDTH as f32 / GRID_COLUMNS as f32;
+const CELL_HEIGHT: f32 = VIEWPORT_HEIGHT as f32 / GRID_ROWS as f32;
+
+// Uniform grid of every entity with a `Collider`, rebuilt on each physics step. Collision
+// systems only test the entities sharing a cell, instead of every pair. As the playfield
+// wraps around, so does the grid: an object near the right edge shares cells with objects
+// near the left edge
+#[derive(Resource)]
+pub(super) struct SpatialHash {
+    cells: Vec<Vec<Entity>>,
+}
+
+impl Default for SpatialHash {
+    fn default() -> Self {
+        SpatialHash {
+            cells: vec![Vec::new(); (GRID_COLUMNS * GRID_ROWS) as usize],
+        }
+    }
+}
+
+impl SpatialHash {
+    fn clear(&mut self) {
+        for cell in &mut self.cells {
+            cell.clear();
+        }
+    }
+
+    fn insert(&mut self, entity: Entity, position: Vec2, radius: f32) {
+        for cell in cells_around(position, radius) {
+            self.cells[cell].push(entity);
+        }
+    }
+
+    // Every entity that may touch a circle, each one only once
+    pub(super) fn query(&self, position: Vec2, radius: f32) -> Vec<Entity> {
+        let mut entities: Vec<Entity> = cells_around(position, radius)
+            .flat_map(|cell| self.cells[cell].iter().copied())
+            .collect();
+        entities.sort_unstable();
+        entities.dedup();
+
+        entities
+    }
+}
+
+// Indices of the cells covered by the bounding box of a circle, wrapped around the grid
+fn cells_around(position: Vec2, radius: f32) -> impl Iterator<Item = usize> {
+    let (min_column, max_column) = cell_range(
+        position.x - VIEWPORT_MIN_X,
+        radius,
+        CELL_WIDTH,
+        GRID_COLUMNS,
+    );
+    let (min_row, max_row) =
+        cell_range(position.y - VIEWPORT_MIN_Y, radius, CELL_HEIGHT, GRID_ROWS);
+
+    (min_row..=max_row).flat_map(move |row| {
+        (min_column..=max_column).map(move |column| {
+            (row.rem_euclid(GRID_ROWS) * GRID_COLUMNS + column.rem_euclid(GRID_COLUMNS)) as usize
+        })
+    })
+}
+
+// First and last cell covered along one axis, not wrapped yet. Covering more cells than the
+// grid has would visit some of them twice, so the range is clamped to one full turn
+fn cell_range(coordinate: f32, radius: f32, cell_size: f32, cell_count: i32) -> (i32, i32) {
+    let min = ((coordinate - radius) / cell_size).floor() as i32;
+    let max = ((coordinate + radius) / cell_size).floor() as i32;
+
+    (min, max.min(min + cell_count - 1))
+}
+
+pub(super) fn rebuild_spatial_hash(
+    mut spatial_hash: ResMut<SpatialHash>,
+    query: Query<(Entity, &Position, &Collider)>,
+) {
+    spatial_hash.clear();
+    for (entity, position, collider) in &query {
+        spatial_hash.insert(entity, position.0, collider.radius);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAX_X: f32 = VIEWPORT_WIDTH as f32 / 2.0;
+    const MAX_Y: f32 = VIEWPORT_HEIGHT as f32 / 2.0;
+
+    #[test]
+    fn query_finds_close_entities_only() {
+        let mut spatial_hash = SpatialHash::default();
+        spatial_hash.insert(Entity::from_raw(1), Vec2::new(10.0, 10.0), 5.0);
+        spatial_hash.insert(Entity::from_raw(2), Vec2::new(400.0, -200.0), 5.0);
+
+        assert_eq!(
+            spatial_hash.query(Vec2::new(0.0, 0.0), 20.0),
+            vec![Entity::from_raw(1)]
+        );
+    }
+
+    #[test]
+    fn query_at_a_corner_finds_entities_at_the_opposite_corner() {
+        let mut spatial_hash = SpatialHash::default();
+        let top_right = Entity::from_raw(1);
+        let middle = Entity::from_raw(2);
+        let bottom_left = Entity::from_raw(3);
+        spatial_hash.insert(top_right, Vec2::new(MAX_X - 5.0, MAX_Y - 5.0), 5.0);
+        spatial_hash.insert(middle, Vec2::ZERO, 5.0);
+        spatial_hash.insert(bottom_left, Vec2::new(-MAX_X + 5.0, -MAX_Y + 5.0), 5.0);
+
+        let found = spatial_hash.query(Vec2::new(-MAX_X + 5.0, -MAX_Y + 5.0), 10.0);
+        assert_eq!(found, vec![top_right, bottom_left]);
+    }
+
+    #[test]
+    fn query_lists_each_entity_once() {
+        let mut spatial_hash = SpatialHash::default();
+        // Big enough to cover several cells, and more than the whole grid
+        spatial_hash.insert(Entity::from_raw(1), Vec2::ZERO, 300.0);
+        spatial_hash.insert(Entity::from_raw(2), Vec2::ZERO, 2000.0);
+
+        assert_eq!(
+            spatial_hash.query(Vec2::ZERO, 300.0),
+            vec![Entity::from_raw(1), Entity::from_raw(2)]
+        );
+    }
+}
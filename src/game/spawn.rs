@@ -0,0 +1,182 @@
+use bevy::{prelude::*, sprite::MaterialMesh2dBundle};
+use rand::Rng;
+
+use super::{
+    get_random_point, random_asteroid_variant, spawn_asteroid, wrap::wrapped_distance,
+    AsteroidSize, GameAssets, OnGameScreen, Position, Starship, VIEWPORT_HEIGHT, VIEWPORT_WIDTH,
+};
+use crate::GamePhase;
+
+// This plugin brings in the asteroids announced by a warning marker on the edge of the screen
+pub struct SpawnPlugin;
+
+impl Plugin for SpawnPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(spawn_incoming_asteroids.in_set(OnUpdate(GamePhase::Playing)));
+    }
+}
+
+// Nothing is spawned closer than this to a ship
+pub(super) const SPAWN_CLEARANCE: f32 = 200.0;
+// Random points tried before settling for the one furthest from every obstacle
+const SPAWN_ATTEMPTS: usize = 32;
+const SPAWN_WARNING_DURATION: f32 = 1.0;
+const SPAWN_WARNING_BLINK_PERIOD: f32 = 0.15;
+const SPAWN_WARNING_SCALE: f32 = 20.0;
+// How far inside the screen the warning marker is drawn, so it is fully visible
+const SPAWN_WARNING_INSET: f32 = 25.0;
+// Pieces of a split asteroid fly off on either side of the hit, within this angle range
+const SPLIT_MIN_ANGLE: f32 = 0.3;
+const SPLIT_MAX_ANGLE: f32 = 0.9;
+
+// A point on the edge of the screen, at least `clearance` away from every obstacle, or as far
+// as possible from them if the screen is too crowded for that
+pub(super) fn safe_edge_point(obstacles: &[Vec2], clearance: f32) -> Vec2 {
+    pick_clear_point(random_edge_point, obstacles, clearance)
+}
+
+// Same as `safe_edge_point`, anywhere on the screen
+pub(super) fn safe_random_point(obstacles: &[Vec2], clearance: f32) -> Vec2 {
+    pick_clear_point(get_random_point, obstacles, clearance)
+}
+
+fn pick_clear_point(random_point: fn() -> Vec2, obstacles: &[Vec2], clearance: f32) -> Vec2 {
+    let clearance_of = |point: Vec2| {
+        obstacles
+            .iter()
+            .map(|obstacle| wrapped_distance(point, *obstacle))
+            .fold(f32::INFINITY, f32::min)
+    };
+
+    let mut best_point = random_point();
+    let mut best_clearance = clearance_of(best_point);
+    for _ in 1..SPAWN_ATTEMPTS {
+        if best_clearance >= clearance {
+            break;
+        }
+        let point = random_point();
+        let point_clearance = clearance_of(point);
+        if point_clearance > best_clearance {
+            best_point = point;
+            best_clearance = point_clearance;
+        }
+    }
+
+    best_point
+}
+
+fn random_edge_point() -> Vec2 {
+    let (half_width, half_height) = (VIEWPORT_WIDTH as f32 / 2.0, VIEWPORT_HEIGHT as f32 / 2.0);
+    let mut rng = rand::thread_rng();
+
+    // The playfield wraps around, so the left and bottom edges are also the right and top ones
+    if rng.gen_bool(0.5) {
+        Vec2::new(-half_width, rng.gen_range(-half_height..half_height))
+    } else {
+        Vec2::new(rng.gen_range(-half_width..half_width), -half_height)
+    }
+}
+
+// Velocity for an asteroid coming in from the edge, aimed somewhere in the middle of the screen
+pub(super) fn inward_velocity(position: Vec2, speed: f32) -> Vec2 {
+    let target = get_random_point() / 2.0;
+    let direction = (target - position).normalize_or_zero();
+    let direction = if direction == Vec2::ZERO {
+        Vec2::X
+    } else {
+        direction
+    };
+
+    direction * speed
+}
+
+// Velocities of the two pieces of an asteroid hit by something moving in `hit_direction`:
+// they carry on in the same general direction, spreading out on either side
+pub(super) fn split_velocities(hit_direction: Vec2, speed: f32) -> [Vec2; 2] {
+    let hit_direction = hit_direction.try_normalize().unwrap_or(Vec2::Y);
+    let mut rng = rand::thread_rng();
+
+    [1.0, -1.0].map(|side| {
+        let angle = side * rng.gen_range(SPLIT_MIN_ANGLE..SPLIT_MAX_ANGLE);
+        Vec2::from_angle(angle).rotate(hit_direction) * speed
+    })
+}
+
+// Warning marker for an asteroid about to come in from the edge of the screen
+#[derive(Component)]
+pub(super) struct IncomingAsteroid {
+    size: AsteroidSize,
+    position: Vec2,
+    velocity: Vec2,
+    timer: Timer,
+    blink: Timer,
+}
+
+// Show a warning marker on the edge, the asteroid itself comes in a moment later
+pub(super) fn spawn_incoming_asteroid(
+    commands: &mut Commands,
+    game_assets: &GameAssets,
+    size: AsteroidSize,
+    position: Vec2,
+    velocity: Vec2,
+) {
+    // The marker is an arrow pointing where the asteroid is headed, pushed inside the screen
+    let direction = velocity.normalize_or_zero();
+    let marker_position = position + direction * SPAWN_WARNING_INSET;
+    let rotation = Vec2::Y.angle_between(direction);
+
+    commands.spawn((
+        IncomingAsteroid {
+            size,
+            position,
+            velocity,
+            timer: Timer::from_seconds(SPAWN_WARNING_DURATION, TimerMode::Once),
+            blink: Timer::from_seconds(SPAWN_WARNING_BLINK_PERIOD, TimerMode::Repeating),
+        },
+        OnGameScreen,
+        MaterialMesh2dBundle {
+            mesh: game_assets.warning_mesh.clone(),
+            transform: Transform::from_translation(marker_position.extend(3.0))
+                .with_rotation(Quat::from_rotation_z(rotation))
+                .with_scale(Vec3::splat(SPAWN_WARNING_SCALE)),
+            material: game_assets.warning_material.clone(),
+            ..default()
+        },
+    ));
+}
+
+// Blink the warning markers, and replace them with their asteroid once their time is up. An
+// asteroid keeps waiting while a ship is too close to where it would appear
+fn spawn_incoming_asteroids(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    time: Res<Time>,
+    mut incoming_query: Query<(Entity, &mut IncomingAsteroid, &mut Visibility)>,
+    starship_query: Query<&Position, With<Starship>>,
+) {
+    for (entity, mut incoming, mut visibility) in &mut incoming_query {
+        if incoming.blink.tick(time.delta()).just_finished() {
+            *visibility = match *visibility {
+                Visibility::Hidden => Visibility::Inherited,
+                _ => Visibility::Hidden,
+            };
+        }
+
+        let too_close = starship_query
+            .iter()
+            .any(|starship| wrapped_distance(starship.0, incoming.position) < SPAWN_CLEARANCE);
+        if !incoming.timer.tick(time.delta()).finished() || too_close {
+            continue;
+        }
+
+        commands.entity(entity).despawn_recursive();
+        spawn_asteroid(
+            &mut commands,
+            &game_assets,
+            incoming.size,
+            random_asteroid_variant(&game_assets, &[]),
+            incoming.position,
+            incoming.velocity,
+        );
+    }
+}
@@ -0,0 +1,321 @@
+use bevy::{
+    prelude::*,
+    render::{
+        mesh::{Indices, VertexAttributeValues},
+        render_resource::PrimitiveTopology,
+    },
+};
+
+use super::{
+    create_asteroid_mesh, create_starship_mesh, quality::QualitySettings, saucer::SaucerSize,
+    AsteroidShape, GameAssets,
+};
+use crate::RenderStyle;
+
+// This plugin applies the render style picked in the settings: flat coloured shapes, or the
+// textured sprites. It only changes how the ship, the saucers and the asteroids are drawn,
+// their collision shapes stay the same in both styles. The textures are drawn on the same
+// outlines as the flat shapes, so what is seen is still what collides
+pub struct RenderStylePlugin;
+
+impl Plugin for RenderStylePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpriteTextures>()
+            .add_system(apply_render_style);
+    }
+}
+
+const FLAT_STARSHIP_COLOR: Color = Color::rgb(1.0, 0.0, 0.0);
+const FLAT_ASTEROID_COLOR: Color = Color::rgb(0.8, 0.8, 0.8);
+const FLAT_LARGE_SAUCER_COLOR: Color = Color::rgb(0.5, 0.9, 0.5);
+const FLAT_SMALL_SAUCER_COLOR: Color = Color::rgb(0.9, 0.5, 0.9);
+// Outline of a flat saucer at a scale of 1.0, a dome on top of a flat hull, within the circle
+// of its collider. Seen from its center, every vertex is in sight, so it can be drawn as a fan
+// of triangles around it
+const FLAT_SAUCER_CENTER: Vec2 = Vec2::new(0.0, 0.05);
+const FLAT_SAUCER_VERTICES: [Vec2; 10] = [
+    Vec2::new(0.4, 0.0),
+    Vec2::new(0.2, 0.15),
+    Vec2::new(0.1, 0.15),
+    Vec2::new(0.07, 0.3),
+    Vec2::new(-0.07, 0.3),
+    Vec2::new(-0.1, 0.15),
+    Vec2::new(-0.2, 0.15),
+    Vec2::new(-0.4, 0.0),
+    Vec2::new(-0.2, -0.15),
+    Vec2::new(0.2, -0.15),
+];
+// Both saucers are drawn in the same part of their texture
+const LARGE_SAUCER_TEXTURE: SpriteTexture = SpriteTexture {
+    path: "textures/simplespace/enemy_A.png",
+    size: Vec2::new(64.0, 64.0),
+    min: Vec2::new(8.0, 8.0),
+    max: Vec2::new(56.0, 56.0),
+};
+const SMALL_SAUCER_TEXTURE: SpriteTexture = SpriteTexture {
+    path: "textures/simplespace/enemy_B.png",
+    size: Vec2::new(64.0, 64.0),
+    min: Vec2::new(8.0, 8.0),
+    max: Vec2::new(56.0, 56.0),
+};
+const STARSHIP_TEXTURE: SpriteTexture = SpriteTexture {
+    path: "textures/simplespace/ship_C.png",
+    size: Vec2::new(64.0, 64.0),
+    min: Vec2::new(8.0, 16.0),
+    max: Vec2::new(56.0, 48.0),
+};
+// Only a solid patch from the middle of each rock is drawn, as the rocks have transparent
+// holes around their edges that would show through the asteroids
+const ROCK_TEXTURES: [SpriteTexture; 6] = [
+    SpriteTexture {
+        path: "textures/rpg/props/generic-rpg-rock01.png",
+        size: Vec2::new(16.0, 8.0),
+        min: Vec2::new(2.0, 2.0),
+        max: Vec2::new(10.0, 6.0),
+    },
+    SpriteTexture {
+        path: "textures/rpg/props/generic-rpg-rock02.png",
+        size: Vec2::new(25.0, 9.0),
+        min: Vec2::new(11.0, 1.0),
+        max: Vec2::new(20.0, 8.0),
+    },
+    SpriteTexture {
+        path: "textures/rpg/props/generic-rpg-rock03.png",
+        size: Vec2::new(23.0, 11.0),
+        min: Vec2::new(5.0, 2.0),
+        max: Vec2::new(13.0, 9.0),
+    },
+    SpriteTexture {
+        path: "textures/rpg/props/generic-rpg-rock04.png",
+        size: Vec2::new(26.0, 15.0),
+        min: Vec2::new(12.0, 2.0),
+        max: Vec2::new(21.0, 12.0),
+    },
+    SpriteTexture {
+        path: "textures/rpg/props/generic-rpg-rock05.png",
+        size: Vec2::new(21.0, 15.0),
+        min: Vec2::new(5.0, 2.0),
+        max: Vec2::new(14.0, 12.0),
+    },
+    SpriteTexture {
+        path: "textures/rpg/props/generic-rpg-rock06.png",
+        size: Vec2::new(32.0, 13.0),
+        min: Vec2::new(12.0, 2.0),
+        max: Vec2::new(21.0, 9.0),
+    },
+];
+// Tints of the rock textures, so that asteroids with the same texture don't all look the same
+const ROCK_TINTS: [Color; 4] = [
+    Color::rgb(0.85, 0.85, 0.85),
+    Color::rgb(0.9, 0.75, 0.6),
+    Color::rgb(0.65, 0.7, 0.8),
+    Color::rgb(0.8, 0.65, 0.65),
+];
+
+// A texture and the part of it drawn on a shape, in pixels
+struct SpriteTexture {
+    path: &'static str,
+    size: Vec2,
+    min: Vec2,
+    max: Vec2,
+}
+
+// Textures drawn in the sprite style, loaded on startup. It will be a resource in the app
+#[derive(Resource)]
+pub(super) struct SpriteTextures {
+    starship: Handle<Image>,
+    large_saucer: Handle<Image>,
+    small_saucer: Handle<Image>,
+    rocks: Vec<Handle<Image>>,
+}
+
+impl FromWorld for SpriteTextures {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.resource::<AssetServer>();
+        SpriteTextures {
+            starship: asset_server.load(STARSHIP_TEXTURE.path),
+            large_saucer: asset_server.load(LARGE_SAUCER_TEXTURE.path),
+            small_saucer: asset_server.load(SMALL_SAUCER_TEXTURE.path),
+            rocks: ROCK_TEXTURES
+                .iter()
+                .map(|texture| asset_server.load(texture.path))
+                .collect(),
+        }
+    }
+}
+
+// Stretch a part of a texture over a flat shape, whose texture coordinates go from 0.0 to 1.0
+// across its bounding box. It stops half a pixel inside that part, so that the filtering
+// doesn't blend in the pixels around it
+fn fit_texture(mut mesh: Mesh, texture: &SpriteTexture) -> Mesh {
+    let min = texture.min + 0.5;
+    let max = texture.max - 0.5;
+    if let Some(VertexAttributeValues::Float32x2(uvs)) = mesh.attribute_mut(Mesh::ATTRIBUTE_UV_0) {
+        for uv in uvs {
+            let pixel = min + Vec2::from(*uv) * (max - min);
+            *uv = (pixel / texture.size).to_array();
+        }
+    }
+
+    mesh
+}
+
+fn create_flat_saucer_mesh() -> Mesh {
+    let positions: Vec<[f32; 3]> = std::iter::once(FLAT_SAUCER_CENTER)
+        .chain(FLAT_SAUCER_VERTICES)
+        .map(|vertex| [vertex.x, vertex.y, 0.0])
+        .collect();
+    // The texture coordinates go across the bounding box of the outline
+    let min = FLAT_SAUCER_VERTICES
+        .into_iter()
+        .fold(Vec2::splat(f32::MAX), Vec2::min);
+    let max = FLAT_SAUCER_VERTICES
+        .into_iter()
+        .fold(Vec2::splat(f32::MIN), Vec2::max);
+    let uvs: Vec<[f32; 2]> = positions
+        .iter()
+        .map(|position| {
+            [
+                (position[0] - min.x) / (max.x - min.x),
+                (max.y - position[1]) / (max.y - min.y),
+            ]
+        })
+        .collect();
+    let vertex_count = FLAT_SAUCER_VERTICES.len() as u32;
+    let indices = (1..=vertex_count)
+        .flat_map(|index| [0, index, index % vertex_count + 1])
+        .collect();
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(
+        Mesh::ATTRIBUTE_NORMAL,
+        vec![[0.0, 0.0, 1.0]; positions.len()],
+    );
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.set_indices(Some(Indices::U32(indices)));
+
+    mesh
+}
+
+pub(super) fn starship_look(
+    style: RenderStyle,
+    textures: &SpriteTextures,
+) -> (Mesh, ColorMaterial) {
+    match style {
+        RenderStyle::Flat => (
+            create_starship_mesh(),
+            ColorMaterial::from(FLAT_STARSHIP_COLOR),
+        ),
+        RenderStyle::Sprites => (
+            fit_texture(create_starship_mesh(), &STARSHIP_TEXTURE),
+            ColorMaterial::from(textures.starship.clone()),
+        ),
+    }
+}
+
+pub(super) fn saucer_look(
+    style: RenderStyle,
+    textures: &SpriteTextures,
+    size: SaucerSize,
+) -> (Mesh, ColorMaterial) {
+    match (style, size) {
+        (RenderStyle::Flat, SaucerSize::Large) => (
+            create_flat_saucer_mesh(),
+            ColorMaterial::from(FLAT_LARGE_SAUCER_COLOR),
+        ),
+        (RenderStyle::Flat, SaucerSize::Small) => (
+            create_flat_saucer_mesh(),
+            ColorMaterial::from(FLAT_SMALL_SAUCER_COLOR),
+        ),
+        (RenderStyle::Sprites, SaucerSize::Large) => (
+            fit_texture(create_flat_saucer_mesh(), &LARGE_SAUCER_TEXTURE),
+            ColorMaterial::from(textures.large_saucer.clone()),
+        ),
+        (RenderStyle::Sprites, SaucerSize::Small) => (
+            fit_texture(create_flat_saucer_mesh(), &SMALL_SAUCER_TEXTURE),
+            ColorMaterial::from(textures.small_saucer.clone()),
+        ),
+    }
+}
+
+// Each of the asteroid variants gets its own rock texture and tint in the sprite style. Both
+// styles follow the display quality for the detail of the outline
+pub(super) fn asteroid_look(
+    style: RenderStyle,
+    textures: &SpriteTextures,
+    quality_settings: &QualitySettings,
+    shape: &AsteroidShape,
+    variant: usize,
+) -> (Mesh, ColorMaterial) {
+    match style {
+        RenderStyle::Flat => (
+            create_asteroid_mesh(shape, quality_settings.asteroid_detail),
+            ColorMaterial::from(FLAT_ASTEROID_COLOR),
+        ),
+        RenderStyle::Sprites => {
+            let rock = variant % ROCK_TEXTURES.len();
+            (
+                fit_texture(
+                    create_asteroid_mesh(shape, quality_settings.asteroid_detail),
+                    &ROCK_TEXTURES[rock],
+                ),
+                ColorMaterial {
+                    color: ROCK_TINTS[variant % ROCK_TINTS.len()],
+                    texture: Some(textures.rocks[rock].clone()),
+                },
+            )
+        }
+    }
+}
+
+// The meshes and materials are shared by every entity using them, replacing them updates
+// everything on the screen at once. The asteroid outlines also follow the display quality
+fn apply_render_style(
+    style: Res<RenderStyle>,
+    quality_settings: Res<QualitySettings>,
+    textures: Res<SpriteTextures>,
+    game_assets: Option<Res<GameAssets>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let Some(game_assets) = game_assets else {
+        return;
+    };
+    if !style.is_changed() && !quality_settings.is_changed() {
+        return;
+    }
+
+    let mut replace = |mesh_handle: &Handle<Mesh>,
+                       material_handle: &Handle<ColorMaterial>,
+                       (mesh, material): (Mesh, ColorMaterial)| {
+        if let Some(old_mesh) = meshes.get_mut(mesh_handle) {
+            *old_mesh = mesh;
+        }
+        if let Some(old_material) = materials.get_mut(material_handle) {
+            *old_material = material;
+        }
+    };
+    replace(
+        &game_assets.starship_mesh.0,
+        &game_assets.starship_material,
+        starship_look(*style, &textures),
+    );
+    replace(
+        &game_assets.saucer_mesh.0,
+        &game_assets.large_saucer_material,
+        saucer_look(*style, &textures, SaucerSize::Large),
+    );
+    replace(
+        &game_assets.saucer_mesh.0,
+        &game_assets.small_saucer_material,
+        saucer_look(*style, &textures, SaucerSize::Small),
+    );
+    for (variant, (shape, mesh, material)) in game_assets.asteroid_variants.iter().enumerate() {
+        replace(
+            &mesh.0,
+            material,
+            asteroid_look(*style, &textures, &quality_settings, shape, variant),
+        );
+    }
+}
@@ -0,0 +1,123 @@
+use bevy::{
+    ecs::system::EntityCommands,
+    prelude::*,
+    render::{mesh::Indices, render_resource::PrimitiveTopology},
+    sprite::MaterialMesh2dBundle,
+};
+use rand::Rng;
+
+use super::{
+    particle::EmitParticles, quality::QualitySettings, GameAssets, Starship, StarshipControls,
+};
+use crate::GamePhase;
+
+// This plugin shows the ship thrusting: a flickering flame behind it, and a trail of exhaust
+// left where it has been
+pub struct ThrusterPlugin;
+
+impl Plugin for ThrusterPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems((flicker_flames, emit_exhaust).in_set(OnUpdate(GamePhase::Playing)));
+    }
+}
+
+// Size of the flame relative to the ship size, its length flickers by up to this factor
+const FLAME_LENGTH: f32 = 0.4;
+const FLAME_WIDTH: f32 = 0.24;
+const FLAME_FLICKER: f32 = 0.35;
+
+// Tag component used to tag the flame drawn behind a ship while it is thrusting
+#[derive(Component)]
+struct Flame;
+
+// A triangle pointing down from its base, so that stretching it only moves the tip
+pub(super) fn create_flame_mesh() -> Mesh {
+    let positions = vec![
+        [-FLAME_WIDTH / 2.0, 0.0, 0.0],
+        [0.0, -FLAME_LENGTH, 0.0],
+        [FLAME_WIDTH / 2.0, 0.0, 0.0],
+    ];
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.set_indices(Some(Indices::U32(vec![0, 1, 2])));
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0, 0.0, 1.0]; 3]);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0, 0.0]; 3]);
+
+    mesh
+}
+
+// Add the flame as a child of a ship, against the back of its triangle and hidden until the
+// ship thrusts
+pub(super) fn spawn_flame(entity: &mut EntityCommands, game_assets: &GameAssets) {
+    entity.with_children(|parent| {
+        parent.spawn((
+            MaterialMesh2dBundle {
+                mesh: game_assets.flame_mesh.clone(),
+                material: game_assets.flame_material.clone(),
+                // Slightly behind the ship
+                transform: Transform::from_xyz(0.0, -0.45, -0.005),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            Flame,
+        ));
+    });
+}
+
+fn flicker_flames(
+    starship_query: Query<&StarshipControls>,
+    mut flame_query: Query<(&Parent, &mut Transform, &mut Visibility), With<Flame>>,
+) {
+    let mut rng = rand::thread_rng();
+    for (parent, mut transform, mut visibility) in &mut flame_query {
+        let thrusting = starship_query
+            .get(parent.get())
+            .is_ok_and(|controls| controls.thrust);
+        if !thrusting {
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+
+        *visibility = Visibility::Inherited;
+        transform.scale = Vec3::new(
+            rng.gen_range(1.0 - FLAME_FLICKER / 2.0..=1.0),
+            rng.gen_range(1.0 - FLAME_FLICKER..=1.0 + FLAME_FLICKER),
+            1.0,
+        );
+    }
+}
+
+// The exhaust is left in the world rather than following the ship, from where the ship is
+// drawn so that the trail lines up with the flame. How dense and long the trail is depends on
+// the display quality
+fn emit_exhaust(
+    time: Res<Time>,
+    quality_settings: Res<QualitySettings>,
+    mut since_last_puff: Local<f32>,
+    mut particles: EventWriter<EmitParticles>,
+    query: Query<(&StarshipControls, &Transform), With<Starship>>,
+) {
+    let Some(period) = quality_settings.exhaust_period else {
+        return;
+    };
+    *since_last_puff += time.delta_seconds();
+    let puffs = (*since_last_puff / period) as usize;
+    *since_last_puff -= puffs as f32 * period;
+
+    for (controls, transform) in &query {
+        if !controls.thrust {
+            continue;
+        }
+        let backward = (transform.rotation * Vec3::NEG_Y).truncate();
+        let position = transform.translation.truncate()
+            + backward * transform.scale.y * (0.45 + FLAME_LENGTH / 2.0);
+        for _ in 0..puffs {
+            particles.send(EmitParticles::exhaust(
+                position,
+                backward,
+                quality_settings.trail_length,
+            ));
+        }
+    }
+}
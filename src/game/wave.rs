@@ -0,0 +1,169 @@
+use bevy::prelude::*;
+
+use super::{
+    spawn::{
+        inward_velocity, safe_edge_point, spawn_incoming_asteroid, IncomingAsteroid,
+        SPAWN_CLEARANCE,
+    },
+    Asteroid, AsteroidSize, GameAssets, Lives, OnGameScreen, Position, Starship, ASTEROID_VELOCITY,
+};
+use crate::{GamePhase, GameState, TEXT_COLOR};
+
+// This plugin spawns the asteroids wave after wave. When the last asteroid of a wave is
+// destroyed, the upgrade shop is opened. Once it is closed, a "WAVE N" banner is shown for a
+// moment before the next, harder, wave comes in from the edges of the screen
+pub struct WavePlugin;
+
+impl Plugin for WavePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WaveCurve>()
+            .add_system(wave_setup.in_schedule(OnEnter(GameState::Game)))
+            .add_system(announce_next_wave.in_schedule(OnExit(GamePhase::Shop)))
+            .add_systems((detect_wave_cleared, spawn_wave).in_set(OnUpdate(GamePhase::Playing)));
+    }
+}
+
+const WAVE_BANNER_DURATION: f32 = 2.0;
+
+// Current wave of asteroids, starting at 1
+#[derive(Resource)]
+pub(super) struct Wave(pub(super) u32);
+
+// How the difficulty grows from one wave to the next: how many big asteroids come in and how
+// fast they fly, both capped. It will be a resource in the app
+#[derive(Resource, Debug, Clone)]
+pub(super) struct WaveCurve {
+    // Number of big asteroids in the first wave
+    pub(super) initial_asteroids: u32,
+    // Number of big asteroids added by each following wave
+    pub(super) asteroids_per_wave: u32,
+    pub(super) max_asteroids: u32,
+    // Asteroid velocity is multiplied by this factor for each following wave
+    pub(super) velocity_growth: f32,
+    pub(super) max_velocity: f32,
+}
+
+impl Default for WaveCurve {
+    fn default() -> Self {
+        WaveCurve {
+            initial_asteroids: 4,
+            asteroids_per_wave: 2,
+            max_asteroids: 12,
+            velocity_growth: 1.1,
+            max_velocity: ASTEROID_VELOCITY * 2.5,
+        }
+    }
+}
+
+impl WaveCurve {
+    pub(super) fn asteroid_count(&self, wave: u32) -> u32 {
+        let extra = self.asteroids_per_wave * wave.saturating_sub(1);
+        (self.initial_asteroids + extra).min(self.max_asteroids)
+    }
+
+    pub(super) fn asteroid_velocity(&self, wave: u32) -> f32 {
+        let growth = self.velocity_growth.powi(wave.saturating_sub(1) as i32);
+        (ASTEROID_VELOCITY * growth).min(self.max_velocity)
+    }
+}
+
+// Delay between a wave being announced and its asteroids coming in
+#[derive(Resource, Deref, DerefMut)]
+struct WaveTimer(Timer);
+
+// Tag component used to tag the "WAVE N" banner
+#[derive(Component)]
+struct OnWaveBanner;
+
+fn wave_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(Wave(1));
+    announce_wave(&mut commands, &asset_server, 1);
+}
+
+fn announce_wave(commands: &mut Commands, asset_server: &AssetServer, wave: u32) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            OnWaveBanner,
+            OnGameScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                format!("WAVE {wave}"),
+                TextStyle {
+                    font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                    font_size: 80.0,
+                    color: TEXT_COLOR,
+                },
+            ));
+        });
+    commands.insert_resource(WaveTimer(Timer::from_seconds(
+        WAVE_BANNER_DURATION,
+        TimerMode::Once,
+    )));
+}
+
+// Open the shop once every asteroid of the current wave has been destroyed. Not when the last
+// ship went down with the last asteroid, the game is over then
+fn detect_wave_cleared(
+    mut wave: ResMut<Wave>,
+    lives: Res<Lives>,
+    mut game_phase: ResMut<NextState<GamePhase>>,
+    wave_timer: Option<Res<WaveTimer>>,
+    asteroids_query: Query<(), Or<(With<Asteroid>, With<IncomingAsteroid>)>>,
+) {
+    if lives.0 > 0 && wave_timer.is_none() && asteroids_query.is_empty() {
+        wave.0 += 1;
+        game_phase.set(GamePhase::Shop);
+    }
+}
+
+fn announce_next_wave(mut commands: Commands, asset_server: Res<AssetServer>, wave: Res<Wave>) {
+    announce_wave(&mut commands, &asset_server, wave.0);
+}
+
+// Tick the banner timer, and bring in the asteroids of the wave when finished
+#[allow(clippy::too_many_arguments)]
+fn spawn_wave(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    time: Res<Time>,
+    wave: Res<Wave>,
+    wave_curve: Res<WaveCurve>,
+    wave_timer: Option<ResMut<WaveTimer>>,
+    banner_query: Query<Entity, With<OnWaveBanner>>,
+    starship_query: Query<&Position, With<Starship>>,
+) {
+    let Some(mut wave_timer) = wave_timer else {
+        return;
+    };
+    if !wave_timer.tick(time.delta()).finished() {
+        return;
+    }
+
+    commands.remove_resource::<WaveTimer>();
+    for entity in &banner_query {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let starships: Vec<Vec2> = starship_query.iter().map(|position| position.0).collect();
+    let velocity = wave_curve.asteroid_velocity(wave.0);
+    for _ in 0..wave_curve.asteroid_count(wave.0) {
+        let position = safe_edge_point(&starships, SPAWN_CLEARANCE);
+        spawn_incoming_asteroid(
+            &mut commands,
+            &game_assets,
+            AsteroidSize::Big,
+            position,
+            inward_velocity(position, velocity),
+        );
+    }
+}
@@ -0,0 +1,160 @@
+use bevy::{
+    ecs::system::EntityCommands,
+    prelude::*,
+    sprite::{Material2d, MaterialMesh2dBundle, Mesh2dHandle},
+};
+
+use super::{collision::Collider, VIEWPORT_HEIGHT, VIEWPORT_MIN_X, VIEWPORT_MIN_Y, VIEWPORT_WIDTH};
+
+// The playfield wraps around on both axes, it is a torus the size of the viewport
+const PLAYFIELD_SIZE: Vec2 = Vec2::new(VIEWPORT_WIDTH as f32, VIEWPORT_HEIGHT as f32);
+const PLAYFIELD_MIN: Vec2 = Vec2::new(VIEWPORT_MIN_X, VIEWPORT_MIN_Y);
+
+fn rem_euclid(value: Vec2, size: Vec2) -> Vec2 {
+    Vec2::new(value.x.rem_euclid(size.x), value.y.rem_euclid(size.y))
+}
+
+// Bring a position that went past an edge back in from the opposite edge
+pub(super) fn wrap_position(position: Vec2) -> Vec2 {
+    PLAYFIELD_MIN + rem_euclid(position - PLAYFIELD_MIN, PLAYFIELD_SIZE)
+}
+
+// Shortest displacement from `from` to `to`, possibly going across the edges
+pub(super) fn wrapped_delta(from: Vec2, to: Vec2) -> Vec2 {
+    rem_euclid(to - from + PLAYFIELD_SIZE / 2.0, PLAYFIELD_SIZE) - PLAYFIELD_SIZE / 2.0
+}
+
+pub(super) fn wrapped_distance(from: Vec2, to: Vec2) -> f32 {
+    wrapped_delta(from, to).length()
+}
+
+// Copy of an object drawn on the other side of the screen while it straddles an edge, so
+// that what is seen matches what collides. Every wrapping object has one ghost for each way
+// it can straddle: across the left/right edges, the top/bottom edges, or a corner
+#[derive(Component, Clone, Copy)]
+pub(super) enum WrapGhost {
+    Horizontal,
+    Vertical,
+    Diagonal,
+}
+
+// Add the ghosts as children of an object, sharing its mesh and material
+pub(super) fn spawn_wrap_ghosts<M: Material2d>(
+    entity: &mut EntityCommands,
+    mesh: &Mesh2dHandle,
+    material: &Handle<M>,
+) {
+    entity.with_children(|parent| {
+        for ghost in [
+            WrapGhost::Horizontal,
+            WrapGhost::Vertical,
+            WrapGhost::Diagonal,
+        ] {
+            parent.spawn((
+                MaterialMesh2dBundle {
+                    mesh: mesh.clone(),
+                    material: material.clone(),
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+                ghost,
+            ));
+        }
+    });
+}
+
+// Show the ghosts of the objects straddling an edge on the opposite side of the screen
+pub(super) fn sync_wrap_ghosts(
+    parent_query: Query<(&Transform, &Collider), Without<WrapGhost>>,
+    mut ghost_query: Query<(&WrapGhost, &Parent, &mut Transform, &mut Visibility)>,
+) {
+    for (ghost, parent, mut transform, mut visibility) in &mut ghost_query {
+        let Ok((parent_transform, collider)) = parent_query.get(parent.get()) else {
+            continue;
+        };
+
+        // Which way the object sticks out of the screen on each axis, if it does
+        let position = parent_transform.translation.truncate() - PLAYFIELD_MIN;
+        let overflow = |coordinate: f32, size: f32| {
+            if coordinate - collider.radius < 0.0 {
+                1.0
+            } else if coordinate + collider.radius > size {
+                -1.0
+            } else {
+                0.0
+            }
+        };
+        let direction = Vec2::new(
+            overflow(position.x, PLAYFIELD_SIZE.x),
+            overflow(position.y, PLAYFIELD_SIZE.y),
+        );
+        let direction = match ghost {
+            WrapGhost::Horizontal => direction * Vec2::X,
+            WrapGhost::Vertical => direction * Vec2::Y,
+            WrapGhost::Diagonal if direction.x == 0.0 || direction.y == 0.0 => Vec2::ZERO,
+            WrapGhost::Diagonal => direction,
+        };
+
+        if direction == Vec2::ZERO {
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+        *visibility = Visibility::Inherited;
+
+        // The ghost is a child, so its offset is expressed in the rotated and scaled
+        // space of the object
+        let offset = (direction * PLAYFIELD_SIZE).extend(0.0);
+        transform.translation =
+            parent_transform.rotation.inverse() * offset / parent_transform.scale;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAX_X: f32 = VIEWPORT_WIDTH as f32 / 2.0;
+    const MAX_Y: f32 = VIEWPORT_HEIGHT as f32 / 2.0;
+
+    #[test]
+    fn wrapped_delta_inside_the_screen() {
+        let delta = wrapped_delta(Vec2::new(-100.0, 50.0), Vec2::new(200.0, -50.0));
+        assert_eq!(delta, Vec2::new(300.0, -100.0));
+    }
+
+    #[test]
+    fn wrapped_delta_across_each_edge() {
+        let right = Vec2::new(MAX_X - 10.0, 0.0);
+        let left = Vec2::new(-MAX_X + 10.0, 0.0);
+        assert_eq!(wrapped_delta(right, left), Vec2::new(20.0, 0.0));
+        assert_eq!(wrapped_delta(left, right), Vec2::new(-20.0, 0.0));
+
+        let top = Vec2::new(0.0, MAX_Y - 10.0);
+        let bottom = Vec2::new(0.0, -MAX_Y + 10.0);
+        assert_eq!(wrapped_delta(top, bottom), Vec2::new(0.0, 20.0));
+        assert_eq!(wrapped_delta(bottom, top), Vec2::new(0.0, -20.0));
+    }
+
+    #[test]
+    fn wrapped_delta_across_a_corner() {
+        let top_right = Vec2::new(MAX_X - 10.0, MAX_Y - 10.0);
+        let bottom_left = Vec2::new(-MAX_X + 10.0, -MAX_Y + 10.0);
+        assert_eq!(wrapped_delta(top_right, bottom_left), Vec2::new(20.0, 20.0));
+        assert_eq!(
+            wrapped_distance(bottom_left, top_right),
+            20.0 * 2.0_f32.sqrt()
+        );
+    }
+
+    #[test]
+    fn wrap_position_comes_back_from_the_opposite_edge() {
+        assert_eq!(
+            wrap_position(Vec2::new(MAX_X + 10.0, -MAX_Y - 10.0)),
+            Vec2::new(-MAX_X + 10.0, MAX_Y - 10.0)
+        );
+        assert_eq!(
+            wrap_position(Vec2::new(12.0, -34.0)),
+            Vec2::new(12.0, -34.0)
+        );
+    }
+}
@@ -0,0 +1,272 @@
+use bevy::prelude::*;
+
+use std::{
+    env, fs,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+// Only the best games are kept in the table
+const MAX_HIGH_SCORES: usize = 10;
+const HIGH_SCORES_FILE: &str = "highscores.txt";
+
+// A single finished game in the high-score table
+#[derive(Debug, Clone, Copy)]
+pub struct HighScore {
+    pub score: u32,
+    pub wave: u32,
+    pub time_survived: Duration,
+    // Seconds since the Unix epoch when the game ended
+    pub date: u64,
+}
+
+impl HighScore {
+    pub fn new(score: u32, wave: u32, time_survived: Duration) -> Self {
+        let date = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |now| now.as_secs());
+
+        HighScore {
+            score,
+            wave,
+            time_survived,
+            date,
+        }
+    }
+
+    // Time survived as `mm:ss`
+    pub fn time_survived_text(&self) -> String {
+        let seconds = self.time_survived.as_secs();
+        format!("{:02}:{:02}", seconds / 60, seconds % 60)
+    }
+
+    // Date the game ended as `yyyy-mm-dd` (UTC)
+    pub fn date_text(&self) -> String {
+        // Convert days since the epoch to a civil date, see
+        // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+        let days = (self.date / 86_400) as i64 + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_index = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+        let month = if month_index < 10 {
+            month_index + 3
+        } else {
+            month_index - 9
+        };
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+        format!("{year:04}-{month:02}-{day:02}")
+    }
+
+    fn to_line(self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}",
+            self.score,
+            self.wave,
+            self.time_survived.as_millis(),
+            self.date
+        )
+    }
+
+    fn from_line(line: &str) -> Option<Self> {
+        let mut fields = line.split('\t');
+        let high_score = HighScore {
+            score: fields.next()?.parse().ok()?,
+            wave: fields.next()?.parse().ok()?,
+            time_survived: Duration::from_millis(fields.next()?.parse().ok()?),
+            date: fields.next()?.parse().ok()?,
+        };
+
+        fields.next().is_none().then_some(high_score)
+    }
+}
+
+// The best games played, sorted from the highest score down. It will be a resource in the app
+#[derive(Resource, Debug, Default)]
+pub struct HighScores(pub Vec<HighScore>);
+
+impl HighScores {
+    // Load the table saved by a previous session. A missing file gives an empty table, and
+    // lines that can't be understood are skipped rather than failing the whole table
+    pub fn load() -> Self {
+        let Some(path) = high_scores_path() else {
+            return HighScores::default();
+        };
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(error) => {
+                if path.exists() {
+                    warn!(
+                        "Could not read high scores from {}: {error}",
+                        path.display()
+                    );
+                }
+                return HighScores::default();
+            }
+        };
+
+        HighScores::parse(&content)
+    }
+
+    fn parse(content: &str) -> Self {
+        let mut high_scores = Vec::new();
+        for line in content.lines().filter(|line| !line.trim().is_empty()) {
+            match HighScore::from_line(line) {
+                Some(high_score) => high_scores.push(high_score),
+                None => warn!("Ignoring corrupt high score entry {line:?}"),
+            }
+        }
+        high_scores.sort_by_key(|high_score| std::cmp::Reverse(high_score.score));
+        high_scores.truncate(MAX_HIGH_SCORES);
+
+        HighScores(high_scores)
+    }
+
+    pub fn save(&self) {
+        let Some(path) = high_scores_path() else {
+            warn!("No data directory found, high scores will not be saved");
+            return;
+        };
+        let content: String = self
+            .0
+            .iter()
+            .map(|high_score| high_score.to_line() + "\n")
+            .collect();
+
+        let result = match path.parent() {
+            Some(directory) => fs::create_dir_all(directory),
+            None => Ok(()),
+        }
+        .and_then(|_| fs::write(&path, content));
+        if let Err(error) = result {
+            warn!("Could not save high scores to {}: {error}", path.display());
+        }
+    }
+
+    // Add a finished game to the table, returning its rank (starting at 1) if it made it in
+    pub fn insert(&mut self, high_score: HighScore) -> Option<usize> {
+        // A game where nothing was shot isn't worth remembering
+        if high_score.score == 0 {
+            return None;
+        }
+        // Ties go below the existing entries, which were there first
+        let index = self
+            .0
+            .iter()
+            .position(|other| other.score < high_score.score)
+            .unwrap_or(self.0.len());
+        if index >= MAX_HIGH_SCORES {
+            return None;
+        }
+
+        self.0.insert(index, high_score);
+        self.0.truncate(MAX_HIGH_SCORES);
+        Some(index + 1)
+    }
+}
+
+// The high scores live in the platform data directory, e.g. `~/.local/share/asteroids` on Linux
+fn high_scores_path() -> Option<PathBuf> {
+    let data_dir = if cfg!(target_os = "windows") {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    } else {
+        env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+    };
+
+    data_dir.map(|data_dir| data_dir.join("asteroids").join(HIGH_SCORES_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn high_score(score: u32, date: u64) -> HighScore {
+        HighScore {
+            score,
+            wave: 1,
+            time_survived: Duration::from_secs(60),
+            date,
+        }
+    }
+
+    #[test]
+    fn parse_skips_corrupt_lines() {
+        let content = "500\t3\t90000\t1000\n\
+                       not a high score\n\
+                       400\t2\t60000\n\
+                       lots\t2\t60000\t1000\n\
+                       300\t2\t60000\t1000\textra\n\
+                       \n\
+                       200\t1\t30000\t1000\n";
+        let high_scores = HighScores::parse(content);
+
+        let scores: Vec<u32> = high_scores.0.iter().map(|entry| entry.score).collect();
+        assert_eq!(scores, vec![500, 200]);
+        assert_eq!(high_scores.0[0].wave, 3);
+        assert_eq!(high_scores.0[0].time_survived, Duration::from_secs(90));
+    }
+
+    #[test]
+    fn parse_sorts_and_keeps_the_best() {
+        let content: String = (1..=12)
+            .map(|score| high_score(score * 100, 0).to_line() + "\n")
+            .collect();
+        let high_scores = HighScores::parse(&content);
+
+        assert_eq!(high_scores.0.len(), MAX_HIGH_SCORES);
+        assert_eq!(high_scores.0[0].score, 1200);
+        assert_eq!(high_scores.0[MAX_HIGH_SCORES - 1].score, 300);
+    }
+
+    #[test]
+    fn insert_keeps_older_ties_first() {
+        let mut high_scores = HighScores::default();
+        assert_eq!(high_scores.insert(high_score(100, 1)), Some(1));
+        assert_eq!(high_scores.insert(high_score(100, 2)), Some(2));
+        assert_eq!(high_scores.insert(high_score(200, 3)), Some(1));
+
+        let dates: Vec<u64> = high_scores.0.iter().map(|entry| entry.date).collect();
+        assert_eq!(dates, vec![3, 1, 2]);
+    }
+
+    #[test]
+    fn insert_cuts_the_table() {
+        let mut high_scores = HighScores::default();
+        for score in 1..=MAX_HIGH_SCORES as u32 {
+            high_scores.insert(high_score(score * 100, 0));
+        }
+
+        assert_eq!(high_scores.insert(high_score(50, 0)), None);
+        assert_eq!(high_scores.insert(high_score(100, 0)), None);
+        assert_eq!(high_scores.insert(high_score(0, 0)), None);
+        assert_eq!(high_scores.insert(high_score(550, 0)), Some(6));
+        assert_eq!(high_scores.0.len(), MAX_HIGH_SCORES);
+        assert_eq!(high_scores.0[MAX_HIGH_SCORES - 1].score, 200);
+    }
+
+    #[test]
+    fn date_text_gives_the_civil_date() {
+        assert_eq!(high_score(0, 0).date_text(), "1970-01-01");
+        assert_eq!(high_score(0, 86_399).date_text(), "1970-01-01");
+        assert_eq!(high_score(0, 951_782_400).date_text(), "2000-02-29");
+        assert_eq!(high_score(0, 1_582_934_400).date_text(), "2020-02-29");
+        assert_eq!(high_score(0, 1_583_020_800).date_text(), "2020-03-01");
+        assert_eq!(high_score(0, 1_704_067_199).date_text(), "2023-12-31");
+    }
+
+    #[test]
+    fn time_survived_text_gives_minutes_and_seconds() {
+        let mut entry = high_score(0, 0);
+        entry.time_survived = Duration::from_millis(754_900);
+        assert_eq!(entry.time_survived_text(), "12:34");
+    }
+}
@@ -1,3 +1,6 @@
+// Bevy systems routinely take queries with several filters
+#![allow(clippy::type_complexity)]
+
 use bevy::{
   prelude::*,
 };
@@ -5,6 +8,7 @@ use bevy::{
 mod splash;
 mod menu;
 mod game;
+mod highscore;
 
 
 #[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Hash, States)]
@@ -15,7 +19,18 @@ enum GameState {
     Game,
 }
 
-// One of the two settings that can be set through the menu. It will be a resource in the app
+// State used for the current phase of a game in progress
+#[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Hash, States)]
+enum GamePhase {
+    Playing,
+    Paused,
+    GameOver,
+    Shop,
+    #[default]
+    Disabled,
+}
+
+// One of the settings that can be set through the menu. It will be a resource in the app
 #[derive(Resource, Debug, Component, PartialEq, Eq, Clone, Copy)]
 enum DisplayQuality {
     Low,
@@ -23,10 +38,30 @@ enum DisplayQuality {
     High,
 }
 
-// One of the two settings that can be set through the menu. It will be a resource in the app
+// One of the settings that can be set through the menu. It will be a resource in the app
 #[derive(Resource, Debug, Component, PartialEq, Eq, Clone, Copy)]
 struct Volume(u32);
 
+// One of the settings that can be set through the menu, whether the screen shakes and flashes
+// during the game. It will be a resource in the app
+#[derive(Resource, Debug, Component, PartialEq, Eq, Clone, Copy)]
+enum ScreenEffects {
+    On,
+    Off,
+}
+
+// One of the settings that can be set through the menu, whether the ship, the saucers and the
+// asteroids are drawn as flat shapes or with textures. It will be a resource in the app
+#[derive(Resource, Debug, Component, PartialEq, Eq, Clone, Copy)]
+enum RenderStyle {
+    Flat,
+    Sprites,
+}
+
+// Tag component used to find the only camera, shared by the menus and the game
+#[derive(Component)]
+struct MainCamera;
+
 
 const TEXT_COLOR: Color = Color::rgb(0.9, 0.9, 0.9);
 
@@ -36,8 +71,12 @@ fn main() {
     .add_plugins(DefaultPlugins)
     .insert_resource(DisplayQuality::Medium)
     .insert_resource(Volume(7))
+    .insert_resource(ScreenEffects::On)
+    .insert_resource(RenderStyle::Flat)
+    .insert_resource(highscore::HighScores::load())
     .add_startup_system(setup)
     .add_state::<GameState>()
+    .add_state::<GamePhase>()
     .add_plugin(splash::SplashPlugin)
     .add_plugin(menu::MenuPlugin)
     .add_plugin(game::GamePlugin)
@@ -45,7 +84,7 @@ fn main() {
 }
 
 fn setup(mut commands: Commands) {
-  commands.spawn(Camera2dBundle::default());
+  commands.spawn((Camera2dBundle::default(), MainCamera));
 }
 
 
@@ -1,11 +1,16 @@
 use bevy::{app::AppExit, prelude::*};
 
-use super::{despawn_screen, DisplayQuality, GameState, Volume, TEXT_COLOR};
+use super::{
+    despawn_screen, highscore::HighScores, DisplayQuality, GamePhase, GameState, RenderStyle,
+    ScreenEffects, Volume, TEXT_COLOR,
+};
 
-// This plugin manages the menu, with 5 different screens:
-// - a main menu with "New Game", "Settings", "Quit"
-// - a settings menu with two submenus and a back button
-// - two settings screen with a setting that can be set and a back button
+// This plugin manages the menu, with 8 different screens:
+// - a main menu with "New Game", "High Scores", "Settings", "Quit"
+// - a high-score table with a back button
+// - a settings menu with three submenus and a back button
+// - three settings screen with the settings that can be set and a back button
+// - a pause menu shown on top of the game with "Resume", "Settings", "Quit to Main Menu"
 pub struct MenuPlugin;
 
 impl Plugin for MenuPlugin {
@@ -21,6 +26,19 @@ impl Plugin for MenuPlugin {
                 main_menu_setup.in_schedule(OnEnter(MenuState::Main)),
                 despawn_screen::<OnMainMenuScreen>.in_schedule(OnExit(MenuState::Main)),
             ))
+            // Systems to handle the pause menu screen, the settings screens are shared with the
+            // main menu
+            .add_systems((
+                pause_setup.in_schedule(OnEnter(GamePhase::Paused)),
+                pause_menu_setup.in_schedule(OnEnter(MenuState::Pause)),
+                resume_on_escape.in_set(OnUpdate(MenuState::Pause)),
+                despawn_screen::<OnPauseMenuScreen>.in_schedule(OnExit(MenuState::Pause)),
+            ))
+            // Systems to handle the high scores screen
+            .add_systems((
+                high_scores_menu_setup.in_schedule(OnEnter(MenuState::HighScores)),
+                despawn_screen::<OnHighScoresMenuScreen>.in_schedule(OnExit(MenuState::HighScores)),
+            ))
             // Systems to handle the settings menu screen
             .add_systems((
                 settings_menu_setup.in_schedule(OnEnter(MenuState::Settings)),
@@ -30,6 +48,7 @@ impl Plugin for MenuPlugin {
             .add_systems((
                 display_settings_menu_setup.in_schedule(OnEnter(MenuState::SettingsDisplay)),
                 setting_button::<DisplayQuality>.in_set(OnUpdate(MenuState::SettingsDisplay)),
+                setting_button::<RenderStyle>.in_set(OnUpdate(MenuState::SettingsDisplay)),
                 despawn_screen::<OnDisplaySettingsMenuScreen>
                     .in_schedule(OnExit(MenuState::SettingsDisplay)),
             ))
@@ -40,8 +59,16 @@ impl Plugin for MenuPlugin {
                 despawn_screen::<OnSoundSettingsMenuScreen>
                     .in_schedule(OnExit(MenuState::SettingsSound)),
             ))
+            // Systems to handle the accessibility settings screen
+            .add_systems((
+                accessibility_settings_menu_setup
+                    .in_schedule(OnEnter(MenuState::SettingsAccessibility)),
+                setting_button::<ScreenEffects>.in_set(OnUpdate(MenuState::SettingsAccessibility)),
+                despawn_screen::<OnAccessibilitySettingsMenuScreen>
+                    .in_schedule(OnExit(MenuState::SettingsAccessibility)),
+            ))
             // Common systems to all screens that handles buttons behaviour
-            .add_systems((menu_action, button_system).in_set(OnUpdate(GameState::Menu)));
+            .add_systems((menu_action, button_system).distributive_run_if(menu_displayed));
     }
 }
 
@@ -49,9 +76,12 @@ impl Plugin for MenuPlugin {
 #[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Hash, States)]
 enum MenuState {
     Main,
+    Pause,
+    HighScores,
     Settings,
     SettingsDisplay,
     SettingsSound,
+    SettingsAccessibility,
     #[default]
     Disabled,
 }
@@ -60,6 +90,14 @@ enum MenuState {
 #[derive(Component)]
 struct OnMainMenuScreen;
 
+// Tag component used to tag entities added on the pause menu screen
+#[derive(Component)]
+struct OnPauseMenuScreen;
+
+// Tag component used to tag entities added on the high scores menu screen
+#[derive(Component)]
+struct OnHighScoresMenuScreen;
+
 // Tag component used to tag entities added on the settings menu screen
 #[derive(Component)]
 struct OnSettingsMenuScreen;
@@ -72,29 +110,37 @@ struct OnDisplaySettingsMenuScreen;
 #[derive(Component)]
 struct OnSoundSettingsMenuScreen;
 
-const NORMAL_BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
+// Tag component used to tag entities added on the accessibility settings menu screen
+#[derive(Component)]
+struct OnAccessibilitySettingsMenuScreen;
+
+pub(crate) const NORMAL_BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
 const HOVERED_BUTTON: Color = Color::rgb(0.25, 0.25, 0.25);
 const HOVERED_PRESSED_BUTTON: Color = Color::rgb(0.25, 0.65, 0.25);
 const PRESSED_BUTTON: Color = Color::rgb(0.35, 0.75, 0.35);
 
 // Tag component used to mark which setting is currently selected
 #[derive(Component)]
-struct SelectedOption;
+pub(crate) struct SelectedOption;
 
 // All actions that can be triggered from a button click
 #[derive(Component)]
 enum MenuButtonAction {
     Play,
+    Resume,
+    HighScores,
     Settings,
     SettingsDisplay,
     SettingsSound,
+    SettingsAccessibility,
     BackToMainMenu,
     BackToSettings,
+    QuitToMainMenu,
     Quit,
 }
 
 // This system handles changing all buttons color based on mouse interaction
-fn button_system(
+pub(crate) fn button_system(
     mut interaction_query: Query<
         (&Interaction, &mut BackgroundColor, Option<&SelectedOption>),
         (Changed<Interaction>, With<Button>),
@@ -111,10 +157,11 @@ fn button_system(
 }
 
 // This system updates the settings when a new value for a setting is selected, and marks
-// the button as the one currently selected
+// the button as the one currently selected. Only the buttons of the same setting are looked
+// at, so a screen can show several settings
 fn setting_button<T: Resource + Component + PartialEq + Copy>(
     interaction_query: Query<(&Interaction, &T, Entity), (Changed<Interaction>, With<Button>)>,
-    mut selected_query: Query<(Entity, &mut BackgroundColor), With<SelectedOption>>,
+    mut selected_query: Query<(Entity, &mut BackgroundColor), (With<SelectedOption>, With<T>)>,
     mut commands: Commands,
     mut setting: ResMut<T>,
 ) {
@@ -129,10 +176,32 @@ fn setting_button<T: Resource + Component + PartialEq + Copy>(
     }
 }
 
+// Condition for the systems common to all screens, as menus can show up both before and
+// during a game
+fn menu_displayed(menu_state: Res<State<MenuState>>) -> bool {
+    menu_state.0 != MenuState::Disabled
+}
+
 fn menu_setup(mut menu_state: ResMut<NextState<MenuState>>) {
     menu_state.set(MenuState::Main);
 }
 
+fn pause_setup(mut menu_state: ResMut<NextState<MenuState>>) {
+    menu_state.set(MenuState::Pause);
+}
+
+// Pressing Escape again on the pause menu goes straight back to the game
+fn resume_on_escape(
+    keys: Res<Input<KeyCode>>,
+    mut menu_state: ResMut<NextState<MenuState>>,
+    mut game_phase: ResMut<NextState<GamePhase>>,
+) {
+    if keys.just_pressed(KeyCode::Escape) {
+        game_phase.set(GamePhase::Playing);
+        menu_state.set(MenuState::Disabled);
+    }
+}
+
 fn main_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
     let font = asset_server.load("fonts/FiraSans-Bold.ttf");
     // Common style for all buttons on the screen
@@ -203,8 +272,9 @@ fn main_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                         }),
                     );
 
-                    // Display three buttons for each action available from the main menu:
+                    // Display four buttons for each action available from the main menu:
                     // - new game
+                    // - high scores
                     // - settings
                     // - quit
                     parent
@@ -228,6 +298,21 @@ fn main_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                                 button_text_style.clone(),
                             ));
                         });
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: button_style.clone(),
+                                background_color: NORMAL_BUTTON.into(),
+                                ..default()
+                            },
+                            MenuButtonAction::HighScores,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(
+                                "High Scores",
+                                button_text_style.clone(),
+                            ));
+                        });
                     parent
                         .spawn((
                             ButtonBundle {
@@ -271,7 +356,91 @@ fn main_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
         });
 }
 
-fn settings_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn pause_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+    let button_style = Style {
+        size: Size::new(Val::Px(350.0), Val::Px(65.0)),
+        margin: UiRect::all(Val::Px(20.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let button_text_style = TextStyle {
+        font: font.clone(),
+        font_size: 40.0,
+        color: TEXT_COLOR,
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            OnPauseMenuScreen,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    background_color: Color::CRIMSON.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(
+                        TextBundle::from_section(
+                            "Paused",
+                            TextStyle {
+                                font,
+                                font_size: 80.0,
+                                color: TEXT_COLOR,
+                            },
+                        )
+                        .with_style(Style {
+                            margin: UiRect::all(Val::Px(50.0)),
+                            ..default()
+                        }),
+                    );
+
+                    for (action, text) in [
+                        (MenuButtonAction::Resume, "Resume"),
+                        (MenuButtonAction::Settings, "Settings"),
+                        (MenuButtonAction::QuitToMainMenu, "Quit to Main Menu"),
+                    ] {
+                        parent
+                            .spawn((
+                                ButtonBundle {
+                                    style: button_style.clone(),
+                                    background_color: NORMAL_BUTTON.into(),
+                                    ..default()
+                                },
+                                action,
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(
+                                    text,
+                                    button_text_style.clone(),
+                                ));
+                            });
+                    }
+                });
+        });
+}
+
+fn high_scores_menu_setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    high_scores: Res<HighScores>,
+) {
     let button_style = Style {
         size: Size::new(Val::Px(200.0), Val::Px(65.0)),
         margin: UiRect::all(Val::Px(20.0)),
@@ -279,6 +448,105 @@ fn settings_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
         align_items: AlignItems::Center,
         ..default()
     };
+    let button_text_style = TextStyle {
+        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+        font_size: 40.0,
+        color: TEXT_COLOR,
+    };
+    // A monospaced font keeps the columns of the table aligned
+    let row_text_style = TextStyle {
+        font: asset_server.load("fonts/FiraMono-Medium.ttf"),
+        font_size: 25.0,
+        color: TEXT_COLOR,
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            OnHighScoresMenuScreen,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        padding: UiRect::all(Val::Px(20.0)),
+                        ..default()
+                    },
+                    background_color: Color::CRIMSON.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(
+                        TextBundle::from_section("High Scores", button_text_style.clone())
+                            .with_style(Style {
+                                margin: UiRect::all(Val::Px(20.0)),
+                                ..default()
+                            }),
+                    );
+
+                    // Display one row per game, or a placeholder if none has been played yet
+                    if high_scores.0.is_empty() {
+                        parent.spawn(TextBundle::from_section(
+                            "No games played yet",
+                            row_text_style.clone(),
+                        ));
+                    } else {
+                        parent.spawn(TextBundle::from_section(
+                            format!(
+                                "{:>2}  {:>7}  {:>4}  {:>5}  {:>10}",
+                                "#", "Score", "Wave", "Time", "Date"
+                            ),
+                            row_text_style.clone(),
+                        ));
+                    }
+                    for (index, high_score) in high_scores.0.iter().enumerate() {
+                        parent.spawn(TextBundle::from_section(
+                            format!(
+                                "{:>2}  {:>7}  {:>4}  {:>5}  {:>10}",
+                                index + 1,
+                                high_score.score,
+                                high_score.wave,
+                                high_score.time_survived_text(),
+                                high_score.date_text()
+                            ),
+                            row_text_style.clone(),
+                        ));
+                    }
+
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: button_style,
+                                background_color: NORMAL_BUTTON.into(),
+                                ..default()
+                            },
+                            MenuButtonAction::BackToMainMenu,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section("Back", button_text_style));
+                        });
+                });
+        });
+}
+
+fn settings_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let button_style = Style {
+        size: Size::new(Val::Px(260.0), Val::Px(65.0)),
+        margin: UiRect::all(Val::Px(20.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
 
     let button_text_style = TextStyle {
         font: asset_server.load("fonts/FiraSans-Bold.ttf"),
@@ -314,6 +582,7 @@ fn settings_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                     for (action, text) in [
                         (MenuButtonAction::SettingsDisplay, "Display"),
                         (MenuButtonAction::SettingsSound, "Sound"),
+                        (MenuButtonAction::SettingsAccessibility, "Accessibility"),
                         (MenuButtonAction::BackToMainMenu, "Back"),
                     ] {
                         parent
@@ -340,6 +609,7 @@ fn display_settings_menu_setup(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     display_quality: Res<DisplayQuality>,
+    render_style: Res<RenderStyle>,
 ) {
     let button_style = Style {
         size: Size::new(Val::Px(200.0), Val::Px(65.0)),
@@ -421,6 +691,40 @@ fn display_settings_menu_setup(
                                 }
                             }
                         });
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            background_color: Color::CRIMSON.into(),
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(
+                                "Render Style",
+                                button_text_style.clone(),
+                            ));
+                            for style_setting in [RenderStyle::Flat, RenderStyle::Sprites] {
+                                let mut entity = parent.spawn(ButtonBundle {
+                                    style: Style {
+                                        size: Size::new(Val::Px(150.0), Val::Px(65.0)),
+                                        ..button_style.clone()
+                                    },
+                                    background_color: NORMAL_BUTTON.into(),
+                                    ..default()
+                                });
+                                entity.insert(style_setting).with_children(|parent| {
+                                    parent.spawn(TextBundle::from_section(
+                                        format!("{style_setting:?}"),
+                                        button_text_style.clone(),
+                                    ));
+                                });
+                                if *render_style == style_setting {
+                                    entity.insert(SelectedOption);
+                                }
+                            }
+                        });
                     // Display the back button to return to the settings screen
                     parent
                         .spawn((
@@ -526,6 +830,99 @@ fn sound_settings_menu_setup(
         });
 }
 
+fn accessibility_settings_menu_setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    screen_effects: Res<ScreenEffects>,
+) {
+    let button_style = Style {
+        size: Size::new(Val::Px(200.0), Val::Px(65.0)),
+        margin: UiRect::all(Val::Px(20.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let button_text_style = TextStyle {
+        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+        font_size: 40.0,
+        color: TEXT_COLOR,
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            OnAccessibilitySettingsMenuScreen,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    background_color: Color::CRIMSON.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            background_color: Color::CRIMSON.into(),
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(
+                                "Screen Shake & Flashes",
+                                button_text_style.clone(),
+                            ));
+                            for effects_setting in [ScreenEffects::On, ScreenEffects::Off] {
+                                let mut entity = parent.spawn(ButtonBundle {
+                                    style: Style {
+                                        size: Size::new(Val::Px(100.0), Val::Px(65.0)),
+                                        ..button_style.clone()
+                                    },
+                                    background_color: NORMAL_BUTTON.into(),
+                                    ..default()
+                                });
+                                entity.insert(effects_setting).with_children(|parent| {
+                                    parent.spawn(TextBundle::from_section(
+                                        format!("{effects_setting:?}"),
+                                        button_text_style.clone(),
+                                    ));
+                                });
+                                if *screen_effects == effects_setting {
+                                    entity.insert(SelectedOption);
+                                }
+                            }
+                        });
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: button_style,
+                                background_color: NORMAL_BUTTON.into(),
+                                ..default()
+                            },
+                            MenuButtonAction::BackToSettings,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section("Back", button_text_style));
+                        });
+                });
+        });
+}
+
 fn menu_action(
     interaction_query: Query<
         (&Interaction, &MenuButtonAction),
@@ -534,6 +931,8 @@ fn menu_action(
     mut app_exit_events: EventWriter<AppExit>,
     mut menu_state: ResMut<NextState<MenuState>>,
     mut game_state: ResMut<NextState<GameState>>,
+    mut game_phase: ResMut<NextState<GamePhase>>,
+    current_game_phase: Res<State<GamePhase>>,
 ) {
     for (interaction, menu_button_action) in &interaction_query {
         if *interaction == Interaction::Clicked {
@@ -543,6 +942,11 @@ fn menu_action(
                     game_state.set(GameState::Game);
                     menu_state.set(MenuState::Disabled);
                 }
+                MenuButtonAction::Resume => {
+                    game_phase.set(GamePhase::Playing);
+                    menu_state.set(MenuState::Disabled);
+                }
+                MenuButtonAction::HighScores => menu_state.set(MenuState::HighScores),
                 MenuButtonAction::Settings => menu_state.set(MenuState::Settings),
                 MenuButtonAction::SettingsDisplay => {
                     menu_state.set(MenuState::SettingsDisplay);
@@ -550,10 +954,26 @@ fn menu_action(
                 MenuButtonAction::SettingsSound => {
                     menu_state.set(MenuState::SettingsSound);
                 }
-                MenuButtonAction::BackToMainMenu => menu_state.set(MenuState::Main),
+                MenuButtonAction::SettingsAccessibility => {
+                    menu_state.set(MenuState::SettingsAccessibility);
+                }
+                // The settings screens are shared with the pause menu, go back where they
+                // were opened from
+                MenuButtonAction::BackToMainMenu => {
+                    if current_game_phase.0 == GamePhase::Paused {
+                        menu_state.set(MenuState::Pause);
+                    } else {
+                        menu_state.set(MenuState::Main);
+                    }
+                }
                 MenuButtonAction::BackToSettings => {
                     menu_state.set(MenuState::Settings);
                 }
+                MenuButtonAction::QuitToMainMenu => {
+                    // Entering the menu state brings up the main menu
+                    game_state.set(GameState::Menu);
+                    game_phase.set(GamePhase::Disabled);
+                }
             }
         }
     }